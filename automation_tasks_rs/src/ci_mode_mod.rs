@@ -0,0 +1,161 @@
+// ci_mode_mod.rs
+
+//! Global `--ci` flag for non-interactive automation tasks.
+//!
+//! With `--ci` the secret tokens are not decrypted with the SSH private key.
+//! They are read from environment variables or from an open file descriptor.
+//! The tasks never prompt. Where the interactive flow asks a question, the task fails fast with a clear error.
+//! Without `--ci` the interactive flow with the tokens encrypted by the SSH key stays the default.
+//!
+//! The upstream-managed modules in encrypt_decrypt_with_ssh_key_mod prompt for the SSH passphrase,
+//! for the OAuth device flow and for the crates.io token. They cannot be changed, so every call into them
+//! from github_api_mod, secrets_mod and rotate_secret_key_mod is guarded with `error_if_ci_mode()`.
+//! The other prompts are guarded at their call sites in github_api_mod and update_automation_tasks_rs_mod.
+//! The GitHub token is read from GITHUB_TOKEN or GITHUB_TOKEN_FD.
+//! The crates.io token is read from CRATES_IO_TOKEN or CRATES_IO_TOKEN_FD by `publish_to_crates_io()`,
+//! use it in a publish task instead of crates_io_api_token_mod::publish_to_crates_io().
+//!
+//! The file descriptor is preferred, because environment variables can leak to child processes:
+//!
+//! ```bash
+//! GITHUB_TOKEN_FD=3 cargo auto github_new_release --ci 3< github_token.txt
+//! ```
+
+use secrecy::SecretString;
+
+use crate::dry_run_mod as dr;
+
+#[allow(unused_imports)]
+use crate::cl::{BLUE, GREEN, RED, RESET, YELLOW};
+use cargo_auto_lib::ShellCommandLimitedDoubleQuotesSanitizerTrait;
+
+/// The environment variable for the GitHub API secret token.
+pub const GITHUB_TOKEN_ENV: &str = "GITHUB_TOKEN";
+/// The environment variable for the crates.io secret token.
+pub const CRATES_IO_TOKEN_ENV: &str = "CRATES_IO_TOKEN";
+
+/// The flag is set only once in the main() function.
+static CI_MODE: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Remove the `--ci` argument from the arguments and set the global flag.
+pub fn extract_ci_argument(args: impl Iterator<Item = String>) -> Vec<String> {
+    args.filter(|arg| {
+        if arg == "--ci" {
+            CI_MODE.store(true, std::sync::atomic::Ordering::Relaxed);
+            println!("  {BLUE}CI mode: the secret tokens are read from the environment and the tasks never prompt.{RESET}");
+            false
+        } else {
+            true
+        }
+    })
+    .collect()
+}
+
+/// true if the `--ci` argument was used
+pub fn is_ci_mode() -> bool {
+    CI_MODE.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/// In CI mode there is nobody to answer the question, so return an error instead of the prompt.
+pub fn error_if_ci_mode(question: &str) -> anyhow::Result<()> {
+    if is_ci_mode() {
+        anyhow::bail!("CI mode cannot prompt: {question} Run the task interactively without --ci.");
+    }
+    Ok(())
+}
+
+/// Read the secret token from the environment variable or from the file descriptor in `{env_var_name}_FD`.
+///
+/// Exactly one of them must be set and the token must not be empty.
+pub fn secret_token_from_environment(env_var_name: &str) -> anyhow::Result<SecretString> {
+    let fd_env_var_name = format!("{env_var_name}_FD");
+    let secret_token = match (std::env::var(env_var_name), std::env::var(&fd_env_var_name)) {
+        (Ok(_), Ok(_)) => anyhow::bail!("CI mode: set only one of the environment variables {env_var_name} or {fd_env_var_name}."),
+        (Ok(secret_token), Err(_)) => secret_token,
+        (Err(_), Ok(fd)) => {
+            let Ok(fd) = fd.trim().parse::<u32>() else {
+                anyhow::bail!("CI mode: {fd_env_var_name} must be the number of an open file descriptor, not `{fd}`.");
+            };
+            std::fs::read_to_string(format!("/dev/fd/{fd}"))
+                .map_err(|err| anyhow::anyhow!("CI mode: cannot read the secret token from file descriptor {fd}: {err}"))?
+        }
+        (Err(_), Err(_)) => anyhow::bail!(
+            "CI mode: the secret token is missing. Set the environment variable {env_var_name} or {fd_env_var_name} with an open file descriptor."
+        ),
+    };
+    // the file usually ends with a newline
+    let secret_token = secret_token.trim();
+    if secret_token.is_empty() {
+        anyhow::bail!("CI mode: the secret token from {env_var_name} or {fd_env_var_name} is empty.");
+    }
+    Ok(SecretString::from(secret_token))
+}
+
+/// Publish to crates.io like crates_io_api_token_mod::publish_to_crates_io().
+///
+/// With `--ci` the secret token comes from the environment instead of the file encrypted with the SSH key.
+#[allow(dead_code)]
+pub fn publish_to_crates_io() -> anyhow::Result<()> {
+    if !is_ci_mode() {
+        return crate::encrypt_decrypt_with_ssh_key_mod::crates_io_api_token_mod::publish_to_crates_io();
+    }
+    let secret_token = secret_token_from_environment(CRATES_IO_TOKEN_ENV)?;
+    // the secret_token is redacted when print on screen
+    dr::ShellCommand::new(r#"cargo publish --token "{secret_token}" "#)?
+        .arg_secret("{secret_token}", &secret_token)?
+        .run()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use secrecy::ExposeSecret;
+
+    // every test uses its own variable names, because tests run in parallel in the same process
+
+    #[test]
+    pub fn test_secret_token_from_environment_variable() {
+        std::env::set_var("TEST_CI_MODE_VARIABLE_TOKEN", "ghp_secret\n");
+        let secret_token = secret_token_from_environment("TEST_CI_MODE_VARIABLE_TOKEN").unwrap();
+        assert_eq!(secret_token.expose_secret(), "ghp_secret");
+
+        std::env::set_var("TEST_CI_MODE_VARIABLE_TOKEN", " ");
+        assert!(secret_token_from_environment("TEST_CI_MODE_VARIABLE_TOKEN")
+            .unwrap_err()
+            .to_string()
+            .contains("is empty"));
+
+        std::env::set_var("TEST_CI_MODE_VARIABLE_TOKEN_FD", "3");
+        assert!(secret_token_from_environment("TEST_CI_MODE_VARIABLE_TOKEN")
+            .unwrap_err()
+            .to_string()
+            .contains("set only one"));
+    }
+
+    #[test]
+    pub fn test_secret_token_missing() {
+        let err = secret_token_from_environment("TEST_CI_MODE_MISSING_TOKEN").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("TEST_CI_MODE_MISSING_TOKEN or TEST_CI_MODE_MISSING_TOKEN_FD"));
+
+        std::env::set_var("TEST_CI_MODE_MISSING_TOKEN_FD", "stdin");
+        let err = secret_token_from_environment("TEST_CI_MODE_MISSING_TOKEN").unwrap_err();
+        assert!(err.to_string().contains("must be the number of an open file descriptor"));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    pub fn test_secret_token_from_file_descriptor() {
+        let path = std::env::temp_dir().join(format!("test_ci_mode_token_{}.txt", std::process::id()));
+        std::fs::write(&path, "crates_io_secret\n").unwrap();
+        let file = std::fs::File::open(&path).unwrap();
+        let fd = std::os::fd::AsRawFd::as_raw_fd(&file);
+
+        std::env::set_var("TEST_CI_MODE_FD_TOKEN_FD", fd.to_string());
+        let secret_token = secret_token_from_environment("TEST_CI_MODE_FD_TOKEN").unwrap();
+        assert_eq!(secret_token.expose_secret(), "crates_io_secret");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
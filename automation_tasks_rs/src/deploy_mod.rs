@@ -0,0 +1,542 @@
+// deploy_mod.rs
+
+//! Deploy targets for publish_to_web.
+//!
+//! The targets are defined in `automation_tasks_rs/deploy_config.json`, so every developer can deploy to their own server.
+//! The `ssh` target copies the files with rsync over SSH and runs the commands on the server over SSH.
+//! The `local_directory` target uses a local folder, so the flow can be tested without a server.
+//! It needs symlinks, so it works only on Linux and macOS. On Windows use the `ssh` target.
+//!
+//! ## Atomic versioned deploys
+//!
+//! Every deploy is uploaded into its own folder named after the `Cargo.toml` version:
+//! `{web_folder}/{package_name}_versions/{version}/`.
+//! The web server serves `{web_folder}/{package_name}`, that is a symlink to the active version.
+//! The symlink is switched atomically: a new symlink is created and then renamed over the old one.
+//! So the visitors never see a half-copied web app and `cargo auto rollback` just switches the symlink back.
+//! Only the newest `keep_versions` are retained, the active version is never deleted.
+//! A version is uploaded first into `{version}.partial` and renamed on success,
+//! so an interrupted upload is never listed as deployed and the next deploy just uploads it again.
+//!
+//! ## One-time migration
+//!
+//! Before the versioned deploys `{web_folder}/{package_name}` was a real folder. The symlink cannot replace it,
+//! so the deploy stops with an error before uploading. Move the old folder away once on the server, for example:
+//! `ssh user@example.com "mv /var/www/example.com/snake_bevy_wasm_pwa /var/www/example.com/snake_bevy_wasm_pwa_old"`
+//! and run publish_to_web again. Delete the old folder after the new deploy works.
+
+use crate::cl;
+use crate::dry_run_mod as dr;
+
+use cargo_auto_lib::ShellCommandLimitedDoubleQuotesSanitizerTrait;
+#[allow(unused_imports)]
+use cl::{BLUE, GREEN, RED, RESET, YELLOW};
+
+/// The version folder gets this suffix until the upload is complete.
+const PARTIAL_UPLOAD_SUFFIX: &str = ".partial";
+
+/// How many versions are retained if the target does not define keep_versions.
+const DEFAULT_KEEP_VERSIONS: usize = 5;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct DeployConfig {
+    pub default_target: String,
+    pub targets: Vec<DeployTarget>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct DeployTarget {
+    pub name: String,
+    #[serde(default = "default_keep_versions")]
+    pub keep_versions: usize,
+    #[serde(flatten)]
+    pub kind: DeployTargetKind,
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeployTargetKind {
+    Ssh {
+        server_username: String,
+        web_server_domain: String,
+        web_folder: String,
+        public_url: String,
+    },
+    LocalDirectory {
+        web_folder: String,
+    },
+}
+
+/// Create the new symlink and rename it over the old one.
+#[cfg(target_family = "unix")]
+fn replace_symlink(link_target: &str, new_link_path: &std::path::Path, link_path: &std::path::Path) -> anyhow::Result<()> {
+    let _ = std::fs::remove_file(new_link_path);
+    std::os::unix::fs::symlink(link_target, new_link_path)?;
+    // rename is atomic on the same file system
+    std::fs::rename(new_link_path, link_path)?;
+    Ok(())
+}
+
+/// Windows cannot rename a symlink atomically over the old one and creating symlinks needs special rights.
+#[cfg(not(target_family = "unix"))]
+fn replace_symlink(_link_target: &str, _new_link_path: &std::path::Path, link_path: &std::path::Path) -> anyhow::Result<()> {
+    anyhow::bail!(
+        "Cannot switch the symlink {}. The local_directory deploy target works only on Linux and macOS, use an ssh target on Windows.",
+        link_path.display()
+    )
+}
+
+/// serde default for keep_versions
+fn default_keep_versions() -> usize {
+    DEFAULT_KEEP_VERSIONS
+}
+
+/// Application state (static) is initialized only once in the main() function.
+///
+/// And then is accessible all over the code.
+pub static DEPLOY_CONFIG: std::sync::OnceLock<DeployConfig> = std::sync::OnceLock::new();
+
+/// Application state (static) is initialized only once in the main() function.
+///
+/// And then is accessible all over the code.
+pub fn deploy_config_initialize() {
+    if DEPLOY_CONFIG.get().is_some() {
+        return;
+    }
+
+    let deploy_config_json = std::fs::read_to_string("automation_tasks_rs/deploy_config.json")
+        .unwrap_or_else(|_| panic!("{RED}Error: The file automation_tasks_rs/deploy_config.json is missing.{RESET}"));
+    let deploy_config: DeployConfig = serde_json::from_str(&deploy_config_json)
+        .unwrap_or_else(|_| panic!("{RED}Error: The content of automation_tasks_rs/deploy_config.json is not correct.{RESET}"));
+    let _ = DEPLOY_CONFIG.set(deploy_config);
+}
+
+impl DeployConfig {
+    /// Find the target by name. Without name returns the default target.
+    pub fn target(&self, target_name: Option<&str>) -> anyhow::Result<&DeployTarget> {
+        let target_name = target_name.unwrap_or(&self.default_target);
+        self.targets.iter().find(|target| target.name == target_name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Deploy target `{target_name}` is not in deploy_config.json. Known targets: {}",
+                self.target_names().join(", ")
+            )
+        })
+    }
+
+    /// The names of all targets for help and completion.
+    pub fn target_names(&self) -> Vec<&str> {
+        self.targets.iter().map(|target| target.name.as_str()).collect()
+    }
+}
+
+impl DeployTarget {
+    /// Upload the source folder as new version, activate it and delete the old versions.
+    /// Returns the url or folder where the deployed web app can be checked.
+    pub fn deploy(&self, source_folder: &str, package_name: &str, version: &str) -> anyhow::Result<String> {
+        check_safe_path_value(source_folder)?;
+        check_safe_path_value(package_name)?;
+        check_safe_path_value(version)?;
+        let versions = self.list_versions(package_name)?;
+        if versions.iter().any(|deployed_version| deployed_version == version) {
+            anyhow::bail!(
+                "Version {version} is already deployed to {}. Build a new version or use rollback.",
+                self.name
+            );
+        }
+        self.check_web_app_path_is_not_a_folder(package_name)?;
+        println!("  {YELLOW}Upload version {version} to {}{RESET}", self.name);
+        self.upload_version(source_folder, package_name, version)?;
+        println!("  {YELLOW}Activate version {version}{RESET}");
+        self.activate_version(package_name, version)?;
+        self.delete_old_versions(package_name)?;
+        // return
+        Ok(self.published_location(package_name))
+    }
+
+    /// Activate the requested version or the version before the active one.
+    /// Returns the activated version.
+    pub fn rollback(&self, package_name: &str, requested_version: Option<&str>) -> anyhow::Result<String> {
+        check_safe_path_value(package_name)?;
+        let versions = self.list_versions(package_name)?;
+        let active_version = self.active_version(package_name)?;
+        let version = rollback_version(&versions, active_version.as_deref(), requested_version)?;
+        self.check_web_app_path_is_not_a_folder(package_name)?;
+        println!("  {YELLOW}Activate version {version} on {}{RESET}", self.name);
+        self.activate_version(package_name, &version)?;
+        // return
+        Ok(version)
+    }
+
+    /// The url or folder where the deployed web app can be checked.
+    pub fn published_location(&self, package_name: &str) -> String {
+        match &self.kind {
+            DeployTargetKind::Ssh { public_url, .. } => format!("{public_url}/{package_name}"),
+            DeployTargetKind::LocalDirectory { web_folder } => format!("{web_folder}/{package_name}"),
+        }
+    }
+
+    /// The deployed versions sorted from the oldest to the newest.
+    pub fn list_versions(&self, package_name: &str) -> anyhow::Result<Vec<String>> {
+        let mut versions: Vec<String> = match &self.kind {
+            DeployTargetKind::Ssh { .. } => {
+                let output = cl::run_shell_command_output(&format!(
+                    r#"{} "ls -1 {}/{package_name}_versions 2>/dev/null || true""#,
+                    self.ssh_prefix()?,
+                    self.web_folder()
+                ));
+                if output.status != 0 {
+                    anyhow::bail!("Cannot list versions on {}: {}", self.name, output.stderr);
+                }
+                output
+                    .stdout
+                    .lines()
+                    .map(|line| line.trim().to_string())
+                    .filter(|line| !line.is_empty() && !line.ends_with(PARTIAL_UPLOAD_SUFFIX))
+                    .collect()
+            }
+            DeployTargetKind::LocalDirectory { web_folder } => {
+                let versions_folder = std::path::Path::new(web_folder).join(format!("{package_name}_versions"));
+                match std::fs::read_dir(versions_folder) {
+                    Ok(entries) => entries
+                        .flatten()
+                        .filter_map(|entry| entry.file_name().to_str().map(|name| name.to_string()))
+                        .filter(|name| !name.ends_with(PARTIAL_UPLOAD_SUFFIX))
+                        .collect(),
+                    Err(_) => vec![],
+                }
+            }
+        };
+        sort_versions(&mut versions);
+        // return
+        Ok(versions)
+    }
+
+    /// The version where the symlink points to.
+    pub fn active_version(&self, package_name: &str) -> anyhow::Result<Option<String>> {
+        let link_target = match &self.kind {
+            DeployTargetKind::Ssh { .. } => {
+                let output = cl::run_shell_command_output(&format!(
+                    r#"{} "readlink {}/{package_name} || true""#,
+                    self.ssh_prefix()?,
+                    self.web_folder()
+                ));
+                output.stdout.trim().to_string()
+            }
+            DeployTargetKind::LocalDirectory { web_folder } => std::fs::read_link(std::path::Path::new(web_folder).join(package_name))
+                .map(|path| path.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        };
+        // return
+        Ok(link_target
+            .strip_prefix(&format!("{package_name}_versions/"))
+            .map(|version| version.to_string()))
+    }
+
+    /// The symlink cannot replace a real folder. Check it before the upload, see the one-time migration.
+    fn check_web_app_path_is_not_a_folder(&self, package_name: &str) -> anyhow::Result<()> {
+        let is_folder = match &self.kind {
+            DeployTargetKind::Ssh { web_folder, .. } => {
+                let output = cl::run_shell_command_output(&format!(
+                    r#"{} "test -d {web_folder}/{package_name} && ! test -L {web_folder}/{package_name} && echo folder || true""#,
+                    self.ssh_prefix()?
+                ));
+                if output.status != 0 {
+                    anyhow::bail!("Cannot check {web_folder}/{package_name} on {}: {}", self.name, output.stderr);
+                }
+                output.stdout.trim() == "folder"
+            }
+            DeployTargetKind::LocalDirectory { web_folder } => {
+                let link_path = std::path::Path::new(web_folder).join(package_name);
+                link_path.is_dir() && !link_path.is_symlink()
+            }
+        };
+        if is_folder {
+            anyhow::bail!(
+                "{}/{package_name} on {} is a folder and not a symlink. Move it away once, before the first versioned deploy.",
+                self.web_folder(),
+                self.name
+            );
+        }
+        Ok(())
+    }
+
+    /// Copy the source folder into the partial version folder and rename it to the version folder on success.
+    fn upload_version(&self, source_folder: &str, package_name: &str, version: &str) -> anyhow::Result<()> {
+        let partial_version = format!("{version}{PARTIAL_UPLOAD_SUFFIX}");
+        match &self.kind {
+            DeployTargetKind::Ssh {
+                server_username,
+                web_server_domain,
+                web_folder,
+                ..
+            } => {
+                dr::run_shell_command(&format!(
+                    r#"{} "mkdir -p {web_folder}/{package_name}_versions""#,
+                    self.ssh_prefix()?
+                ))?;
+                // rsync to copy to server over ssh into the partial version folder
+                // a partial folder from an interrupted upload is reused, rsync copies only the missing files
+                dr::ShellCommand::new(
+                    r#"rsync -e ssh -a --info=progress2 --delete-after "{source_folder}/" "{server__username}@{web__server__domain}:{web_folder}/{package_name}_versions/{partial_version}/" "#,
+                )?
+                .arg("{source_folder}", source_folder)?
+                .arg("{package_name}", package_name)?
+                .arg("{server__username}", server_username)?
+                .arg("{web__server__domain}", web_server_domain)?
+                .arg("{web_folder}", web_folder)?
+                .arg("{partial_version}", &partial_version)?
+                .run()?;
+                dr::run_shell_command(&format!(
+                    r#"{} "mv -T {web_folder}/{package_name}_versions/{partial_version} {web_folder}/{package_name}_versions/{version}""#,
+                    self.ssh_prefix()?
+                ))?;
+            }
+            DeployTargetKind::LocalDirectory { web_folder } => {
+                let versions_folder = std::path::Path::new(web_folder).join(format!("{package_name}_versions"));
+                let partial_version_folder = versions_folder.join(&partial_version);
+                if !dr::skip(&format!("copy {source_folder} to {}", versions_folder.join(version).display())) {
+                    // the partial folder from an interrupted upload is replaced
+                    if partial_version_folder.exists() {
+                        std::fs::remove_dir_all(&partial_version_folder)?;
+                    }
+                    copy_folder_recursive(std::path::Path::new(source_folder), &partial_version_folder)?;
+                    std::fs::rename(&partial_version_folder, versions_folder.join(version))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Switch the symlink atomically: create a temporary symlink and rename it over the old one.
+    fn activate_version(&self, package_name: &str, version: &str) -> anyhow::Result<()> {
+        check_safe_path_value(version)?;
+        let link_target = format!("{package_name}_versions/{version}");
+        match &self.kind {
+            DeployTargetKind::Ssh { web_folder, .. } => {
+                dr::run_shell_command(&format!(
+                    r#"{} "ln -sfn {link_target} {web_folder}/{package_name}.new_link && mv -T {web_folder}/{package_name}.new_link {web_folder}/{package_name}""#,
+                    self.ssh_prefix()?
+                ))?;
+            }
+            DeployTargetKind::LocalDirectory { web_folder } => {
+                let web_folder = std::path::Path::new(web_folder);
+                let link_path = web_folder.join(package_name);
+                if dr::skip(&format!("switch symlink {} to {link_target}", link_path.display())) {
+                    return Ok(());
+                }
+                let new_link_path = web_folder.join(format!("{package_name}.new_link"));
+                replace_symlink(&link_target, &new_link_path, &link_path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Retention policy: keep only the newest keep_versions and never the active version.
+    fn delete_old_versions(&self, package_name: &str) -> anyhow::Result<()> {
+        let versions = self.list_versions(package_name)?;
+        let active_version = self.active_version(package_name)?;
+        for version in versions_to_delete(&versions, active_version.as_deref(), self.keep_versions) {
+            check_safe_path_value(&version)?;
+            println!("  {YELLOW}Delete old version {version}{RESET}");
+            match &self.kind {
+                DeployTargetKind::Ssh { web_folder, .. } => {
+                    dr::run_shell_command(&format!(
+                        r#"{} "rm -rf {web_folder}/{package_name}_versions/{version}""#,
+                        self.ssh_prefix()?
+                    ))?;
+                }
+                DeployTargetKind::LocalDirectory { web_folder } => {
+                    let version_folder = std::path::Path::new(web_folder)
+                        .join(format!("{package_name}_versions"))
+                        .join(&version);
+                    if !dr::skip(&format!("delete {}", version_folder.display())) {
+                        std::fs::remove_dir_all(version_folder)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The folder that contains the symlink and the versions folder.
+    fn web_folder(&self) -> &str {
+        match &self.kind {
+            DeployTargetKind::Ssh { web_folder, .. } => web_folder,
+            DeployTargetKind::LocalDirectory { web_folder } => web_folder,
+        }
+    }
+
+    /// The start of the ssh command. All the values from the config are checked,
+    /// because the remote command is not sanitized by ShellCommandLimitedDoubleQuotesSanitizer.
+    fn ssh_prefix(&self) -> anyhow::Result<String> {
+        let DeployTargetKind::Ssh {
+            server_username,
+            web_server_domain,
+            web_folder,
+            ..
+        } = &self.kind
+        else {
+            anyhow::bail!("Deploy target {} is not an ssh target.", self.name);
+        };
+        check_safe_path_value(server_username)?;
+        check_safe_path_value(web_server_domain)?;
+        check_safe_path_value(web_folder)?;
+        // return
+        Ok(format!("ssh {server_username}@{web_server_domain}"))
+    }
+}
+
+/// Only simple characters are allowed in names, versions and paths used in shell commands.
+fn check_safe_path_value(value: &str) -> anyhow::Result<()> {
+    let is_safe = !value.is_empty()
+        && value != "."
+        && !value.split('/').any(|segment| segment == "..")
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '/' | '~'));
+    if !is_safe {
+        anyhow::bail!("The value `{value}` from deploy_config.json or Cargo.toml is not allowed in a shell command.");
+    }
+    Ok(())
+}
+
+/// Sort versions like 1.2.10 or 2025.813.1012 by their numbers.
+fn sort_versions(versions: &mut [String]) {
+    versions.sort_by_key(|version| {
+        version
+            .split('.')
+            .map(|number| number.parse::<u64>().unwrap_or(0))
+            .collect::<Vec<u64>>()
+    });
+}
+
+/// The versions that are too old to keep. Versions must be sorted from the oldest.
+fn versions_to_delete(versions: &[String], active_version: Option<&str>, keep_versions: usize) -> Vec<String> {
+    let delete_count = versions.len().saturating_sub(keep_versions.max(1));
+    versions
+        .iter()
+        .take(delete_count)
+        .filter(|version| Some(version.as_str()) != active_version)
+        .cloned()
+        .collect()
+}
+
+/// The requested version must exist. Without request it is the version before the active one.
+fn rollback_version(versions: &[String], active_version: Option<&str>, requested_version: Option<&str>) -> anyhow::Result<String> {
+    if let Some(requested_version) = requested_version {
+        if !versions.iter().any(|version| version == requested_version) {
+            anyhow::bail!(
+                "Version {requested_version} is not deployed. Deployed versions: {}",
+                versions.join(", ")
+            );
+        }
+        return Ok(requested_version.to_string());
+    }
+    let Some(active_position) = versions.iter().position(|version| Some(version.as_str()) == active_version) else {
+        anyhow::bail!("There is no active version to rollback from.");
+    };
+    if active_position == 0 {
+        anyhow::bail!("The active version {} is the oldest deployed version.", versions[0]);
+    }
+    // return
+    Ok(versions[active_position - 1].clone())
+}
+
+/// Copy all files and subfolders. The destination folder must not exist yet.
+fn copy_folder_recursive(source: &std::path::Path, destination: &std::path::Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(destination)?;
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        let destination_path = destination.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_folder_recursive(&entry.path(), &destination_path)?;
+        } else {
+            std::fs::copy(entry.path(), destination_path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_deploy_config_target() {
+        let deploy_config: DeployConfig = serde_json::from_str(
+            r#"{"default_target": "local", "targets": [
+                {"name": "local", "type": "local_directory", "web_folder": "tmp/local_web_server"},
+                {"name": "prod", "type": "ssh", "server_username": "user", "web_server_domain": "example.com",
+                 "web_folder": "/var/www/example.com", "public_url": "https://example.com", "keep_versions": 3}
+            ]}"#,
+        )
+        .unwrap();
+        let local = deploy_config.target(None).unwrap();
+        assert!(matches!(local.kind, DeployTargetKind::LocalDirectory { .. }));
+        assert_eq!(local.keep_versions, DEFAULT_KEEP_VERSIONS);
+        assert_eq!(deploy_config.target(Some("prod")).unwrap().keep_versions, 3);
+        assert!(deploy_config.target(Some("staging")).is_err());
+        assert_eq!(deploy_config.target_names(), vec!["local", "prod"]);
+    }
+
+    #[test]
+    pub fn test_versions_retention_and_rollback() {
+        let mut versions: Vec<String> = ["0.0.10", "0.0.9", "0.0.11", "0.0.8"].iter().map(|v| v.to_string()).collect();
+        sort_versions(&mut versions);
+        assert_eq!(versions, vec!["0.0.8", "0.0.9", "0.0.10", "0.0.11"]);
+        assert_eq!(versions_to_delete(&versions, Some("0.0.11"), 2), vec!["0.0.8", "0.0.9"]);
+        // after a rollback the active version is old, but it is never deleted
+        assert_eq!(versions_to_delete(&versions, Some("0.0.8"), 2), vec!["0.0.9"]);
+        assert_eq!(rollback_version(&versions, Some("0.0.11"), None).unwrap(), "0.0.10");
+        assert_eq!(rollback_version(&versions, Some("0.0.11"), Some("0.0.8")).unwrap(), "0.0.8");
+        assert!(rollback_version(&versions, Some("0.0.8"), None).is_err());
+        assert!(rollback_version(&versions, Some("0.0.11"), Some("1.0.0")).is_err());
+        assert!(check_safe_path_value("../etc").is_err());
+        assert!(check_safe_path_value("0.0.11; rm -rf").is_err());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    pub fn test_local_directory_deploy_and_rollback() {
+        let test_folder = std::env::temp_dir().join(format!("automation_tasks_rs_deploy_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&test_folder);
+        let source_folder = test_folder.join("web_server_folder/test_app");
+        std::fs::create_dir_all(&source_folder).unwrap();
+        let deployed_index = test_folder.join("deployed/test_app/index.html");
+
+        let target = DeployTarget {
+            name: "local".to_string(),
+            keep_versions: 2,
+            kind: DeployTargetKind::LocalDirectory {
+                web_folder: test_folder.join("deployed").to_string_lossy().to_string(),
+            },
+        };
+        let source_folder_str = source_folder.to_string_lossy().to_string();
+
+        // a real folder from before the versioned deploys stops the deploy before the upload
+        std::fs::create_dir_all(test_folder.join("deployed/test_app")).unwrap();
+        assert!(target.deploy(&source_folder_str, "test_app", "1.0.1").is_err());
+        assert!(target.list_versions("test_app").unwrap().is_empty());
+        std::fs::remove_dir_all(test_folder.join("deployed/test_app")).unwrap();
+
+        // an interrupted upload is not a deployed version and is replaced by the next upload
+        let partial_version_folder = test_folder.join("deployed/test_app_versions/1.0.1.partial");
+        std::fs::create_dir_all(&partial_version_folder).unwrap();
+        std::fs::write(partial_version_folder.join("half_copied.js"), "").unwrap();
+        assert!(target.list_versions("test_app").unwrap().is_empty());
+
+        for version in ["1.0.1", "1.0.2", "1.0.3"] {
+            std::fs::write(source_folder.join("index.html"), version).unwrap();
+            target.deploy(&source_folder_str, "test_app", version).unwrap();
+        }
+        assert!(target.deploy(&source_folder_str, "test_app", "1.0.3").is_err());
+        assert_eq!(target.list_versions("test_app").unwrap(), vec!["1.0.2", "1.0.3"]);
+        assert_eq!(std::fs::read_to_string(&deployed_index).unwrap(), "1.0.3");
+        assert_eq!(target.rollback("test_app", None).unwrap(), "1.0.2");
+        assert_eq!(std::fs::read_to_string(&deployed_index).unwrap(), "1.0.2");
+        assert_eq!(target.active_version("test_app").unwrap().as_deref(), Some("1.0.2"));
+        assert!(!partial_version_folder.exists());
+
+        std::fs::remove_dir_all(&test_folder).unwrap();
+    }
+}
@@ -0,0 +1,353 @@
+// doctor_mod.rs
+
+//! Preflight checks for the tools and the configuration of the workflow.
+//!
+//! The tasks call external tools and read config files. When something is missing,
+//! they fail in the middle of the work. `cargo auto doctor` checks everything upfront
+//! and prints an actionable fix for every problem.
+//! The config files are read here directly, because main() panics on invalid config files for the other tasks.
+
+use crate::cl;
+use crate::deploy_mod::DeployConfig;
+use crate::encrypt_decrypt_with_ssh_key_mod::crates_io_api_token_mod::CratesIoConfig;
+use crate::encrypt_decrypt_with_ssh_key_mod::encrypt_decrypt_mod as ende;
+use crate::encrypt_decrypt_with_ssh_key_mod::github_api_token_with_oauth2_mod::GithubApiConfig;
+use crate::wasm_size_mod::WasmSizeConfig;
+
+#[allow(unused_imports)]
+use cl::{BLUE, GREEN, RED, RESET, YELLOW};
+
+/// An external tool and its minimum version.
+struct ToolRequirement {
+    command: &'static str,
+    minimum_version: &'static str,
+    used_for: &'static str,
+    fix: &'static str,
+    /// Optional tools only produce a warning.
+    is_optional: bool,
+}
+
+/// All the tools the tasks call. `ssh -V` prints to stderr, the version is searched in both outputs.
+const TOOLS: [ToolRequirement; 9] = [
+    ToolRequirement {
+        command: "git",
+        minimum_version: "2.28.0",
+        used_for: "commit_and_push, publish_to_web, github_new_release",
+        fix: "sudo apt-get install -y git",
+        is_optional: false,
+    },
+    ToolRequirement {
+        command: "wasm-pack",
+        minimum_version: "0.12.0",
+        used_for: "build, release",
+        fix: "cargo install wasm-pack",
+        is_optional: false,
+    },
+    ToolRequirement {
+        command: "rsync",
+        minimum_version: "3.1.0",
+        used_for: "build, release, doc, publish_to_web",
+        fix: "sudo apt-get install -y rsync",
+        is_optional: false,
+    },
+    ToolRequirement {
+        command: "ssh",
+        minimum_version: "8.0",
+        used_for: "git push over SSH, publish_to_web to ssh targets",
+        fix: "sudo apt-get install -y openssh-client",
+        is_optional: false,
+    },
+    ToolRequirement {
+        command: "tar",
+        minimum_version: "1.28",
+        used_for: "github_new_release web app assets",
+        fix: "sudo apt-get install -y tar",
+        is_optional: false,
+    },
+    ToolRequirement {
+        command: "zip",
+        minimum_version: "3.0",
+        used_for: "github_new_release web app assets",
+        fix: "sudo apt-get install -y zip",
+        is_optional: false,
+    },
+    ToolRequirement {
+        command: "sha256sum",
+        minimum_version: "8.0",
+        used_for: "github_new_release web app assets",
+        fix: "sudo apt-get install -y coreutils",
+        is_optional: false,
+    },
+    ToolRequirement {
+        command: "tidy",
+        minimum_version: "5.6.0",
+        used_for: "doc",
+        fix: "sudo apt-get install -y tidy",
+        is_optional: false,
+    },
+    ToolRequirement {
+        command: "basic-http-server",
+        minimum_version: "0.8.0",
+        used_for: "manual testing, `cargo auto serve` does not need it",
+        fix: "cargo install basic-http-server",
+        is_optional: true,
+    },
+];
+
+/// The result of one check.
+#[derive(Debug, PartialEq)]
+enum Check {
+    Ok(String),
+    Warning { problem: String, fix: String },
+    Error { problem: String, fix: String },
+}
+
+impl Check {
+    /// Missing optional things are warnings, the others are errors.
+    fn problem(is_optional: bool, problem: String, fix: String) -> Self {
+        if is_optional {
+            Check::Warning { problem, fix }
+        } else {
+            Check::Error { problem, fix }
+        }
+    }
+
+    fn print(&self) {
+        match self {
+            Check::Ok(message) => println!("  {GREEN}ok{RESET}      {message}"),
+            Check::Warning { problem, fix } => {
+                println!("  {YELLOW}warning{RESET} {problem}");
+                println!("{GREEN}{fix}{RESET}");
+            }
+            Check::Error { problem, fix } => {
+                println!("  {RED}error{RESET}   {problem}");
+                println!("{GREEN}{fix}{RESET}");
+            }
+        }
+    }
+}
+
+/// Run all checks, print them and return an error if any check failed.
+pub fn doctor() -> anyhow::Result<()> {
+    let mut checks = vec![];
+    println!("  {YELLOW}Tools:{RESET}");
+    for tool in TOOLS.iter() {
+        checks.push(check_tool(tool));
+        checks.last().unwrap().print();
+    }
+    checks.push(check_wasm32_target());
+    checks.last().unwrap().print();
+
+    println!("  {YELLOW}Config files:{RESET}");
+    let github_api_config = read_config::<GithubApiConfig>("automation_tasks_rs/github_api_config.json", &mut checks);
+    let crates_io_config = read_config::<CratesIoConfig>("automation_tasks_rs/crates_io_config.json", &mut checks);
+    if let Some(deploy_config) = read_config::<DeployConfig>("automation_tasks_rs/deploy_config.json", &mut checks) {
+        checks.push(match deploy_config.target(None) {
+            Ok(target) => Check::Ok(format!("default deploy target: {}", target.name)),
+            Err(err) => Check::Error {
+                problem: err.to_string(),
+                fix: "Set default_target in automation_tasks_rs/deploy_config.json to one of the targets.".to_string(),
+            },
+        });
+        checks.last().unwrap().print();
+    }
+
+    let _ = read_config::<WasmSizeConfig>("automation_tasks_rs/wasm_size_config.json", &mut checks);
+
+    println!("  {YELLOW}SSH keys in ~/.ssh:{RESET}");
+    if crate::ci_mode_mod::is_ci_mode() {
+        println!("  {YELLOW}CI mode: the secret tokens come from the environment, the SSH keys are not needed.{RESET}");
+    } else {
+        if let Some(github_api_config) = github_api_config {
+            checks.push(check_ssh_key(
+                &github_api_config.github_api_private_key_file_name,
+                "github api secret_token",
+                false,
+            ));
+            checks.last().unwrap().print();
+        }
+        if let Some(crates_io_config) = crates_io_config {
+            // only libraries are published to crates.io
+            checks.push(check_ssh_key(
+                &crates_io_config.crates_io_private_key_file_name,
+                "crates.io secret_token",
+                true,
+            ));
+            checks.last().unwrap().print();
+        }
+    }
+
+    let errors = checks.iter().filter(|check| matches!(check, Check::Error { .. })).count();
+    let warnings = checks.iter().filter(|check| matches!(check, Check::Warning { .. })).count();
+    if errors > 0 {
+        anyhow::bail!("cargo auto doctor found {errors} errors and {warnings} warnings. Fix the errors above.");
+    }
+    println!("  {GREEN}All required tools and config files are ready. Warnings: {warnings}{RESET}");
+    Ok(())
+}
+
+/// Run the tool with --version (ssh uses -V) and compare with the minimum version.
+fn check_tool(tool: &ToolRequirement) -> Check {
+    let version_arg = if tool.command == "ssh" { "-V" } else { "--version" };
+    let output = match std::process::Command::new(tool.command).arg(version_arg).output() {
+        Ok(output) => output,
+        Err(_) => {
+            return Check::problem(
+                tool.is_optional,
+                format!("{} is not installed. It is used for: {}.", tool.command, tool.used_for),
+                tool.fix.to_string(),
+            )
+        }
+    };
+    let output_text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let Some(version) = parse_version(&output_text) else {
+        return Check::problem(
+            tool.is_optional,
+            format!("Cannot read the version of {} from: {}", tool.command, output_text.trim()),
+            tool.fix.to_string(),
+        );
+    };
+    if is_version_older(&version, tool.minimum_version) {
+        return Check::problem(
+            tool.is_optional,
+            format!(
+                "{} {version} is older than the minimum version {}.",
+                tool.command, tool.minimum_version
+            ),
+            tool.fix.to_string(),
+        );
+    }
+    Check::Ok(format!("{} {version}", tool.command))
+}
+
+/// wasm-pack needs the rust target for wasm.
+fn check_wasm32_target() -> Check {
+    let fix = "rustup target add wasm32-unknown-unknown".to_string();
+    match std::process::Command::new("rustup")
+        .args(["target", "list", "--installed"])
+        .output()
+    {
+        Ok(output)
+            if String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .any(|line| line.trim() == "wasm32-unknown-unknown") =>
+        {
+            Check::Ok("rust target wasm32-unknown-unknown".to_string())
+        }
+        Ok(_) => Check::Error {
+            problem: "The rust target wasm32-unknown-unknown is not installed.".to_string(),
+            fix,
+        },
+        Err(_) => Check::Warning {
+            problem: "rustup is not installed, cannot check the target wasm32-unknown-unknown.".to_string(),
+            fix,
+        },
+    }
+}
+
+/// Read and parse the config file. The problem is recorded in checks.
+fn read_config<T: serde::de::DeserializeOwned>(path: &str, checks: &mut Vec<Check>) -> Option<T> {
+    let (check, config) = match std::fs::read_to_string(path) {
+        Err(_) => (
+            Check::Error {
+                problem: format!("The file {path} is missing."),
+                fix: format!("Copy {path} from the template project cargo-auto and fill in your values."),
+            },
+            None,
+        ),
+        Ok(config_json) => match serde_json::from_str::<T>(&config_json) {
+            Err(err) => (
+                Check::Error {
+                    problem: format!("The content of {path} is not correct: {err}"),
+                    fix: format!("Correct the json in {path}."),
+                },
+                None,
+            ),
+            Ok(config) => (Check::Ok(path.to_string()), Some(config)),
+        },
+    };
+    check.print();
+    checks.push(check);
+    config
+}
+
+/// The private key and its public key must exist for the encrypted secret tokens.
+fn check_ssh_key(private_key_file_name: &str, comment: &str, is_optional: bool) -> Check {
+    let fix = format!(r#"ssh-keygen -t ed25519 -f ~/.ssh/{private_key_file_name} -C "{comment}""#);
+    let exists = |file_name: String| {
+        ende::PathStructInSshFolder::new(file_name)
+            .map(|path| path.exists())
+            .unwrap_or(false)
+    };
+    if !exists(private_key_file_name.to_string()) {
+        return Check::problem(
+            is_optional,
+            format!("Private key ~/.ssh/{private_key_file_name} does not exist."),
+            fix,
+        );
+    }
+    if !exists(format!("{private_key_file_name}.pub")) {
+        return Check::problem(
+            is_optional,
+            format!("Public key ~/.ssh/{private_key_file_name}.pub does not exist. ssh-agent needs it."),
+            format!("ssh-keygen -y -f ~/.ssh/{private_key_file_name} > ~/.ssh/{private_key_file_name}.pub"),
+        );
+    }
+    Check::Ok(format!("~/.ssh/{private_key_file_name}"))
+}
+
+/// The first number with dots in the text, like 3.2.7 in `rsync  version 3.2.7  protocol version 31`.
+fn parse_version(text: &str) -> Option<String> {
+    text.split(|c: char| !c.is_ascii_digit() && c != '.')
+        .map(|word| word.trim_matches('.'))
+        .find(|word| word.contains('.') && word.split('.').all(|part| !part.is_empty()))
+        .map(|word| word.to_string())
+}
+
+/// Compare the numbers one by one. Missing numbers are zero.
+fn is_version_older(version: &str, minimum_version: &str) -> bool {
+    let numbers = |version: &str| -> Vec<u64> { version.split('.').map(|part| part.parse().unwrap_or(0)).collect() };
+    let (mut version, mut minimum_version) = (numbers(version), numbers(minimum_version));
+    let len = version.len().max(minimum_version.len());
+    version.resize(len, 0);
+    minimum_version.resize(len, 0);
+    version < minimum_version
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_parse_version() {
+        assert_eq!(parse_version("git version 2.43.0").as_deref(), Some("2.43.0"));
+        assert_eq!(parse_version("rsync  version 3.2.7  protocol version 31").as_deref(), Some("3.2.7"));
+        assert_eq!(
+            parse_version("OpenSSH_9.6p1 Ubuntu-3ubuntu13.5, OpenSSL 3.0.13 30 Jan 2024").as_deref(),
+            Some("9.6")
+        );
+        assert_eq!(parse_version("HTML Tidy for Linux version 5.8.0").as_deref(), Some("5.8.0"));
+        assert_eq!(parse_version("wasm-pack 0.13.1").as_deref(), Some("0.13.1"));
+        assert_eq!(parse_version("tar (GNU tar) 1.34").as_deref(), Some("1.34"));
+        assert_eq!(
+            parse_version("Copyright (c) 1990-2008 Info-ZIP - Type 'zip \"-L\"' for software license.\nThis is Zip 3.0 (July 5th 2008)")
+                .as_deref(),
+            Some("3.0")
+        );
+        assert_eq!(parse_version("sha256sum (GNU coreutils) 9.1").as_deref(), Some("9.1"));
+        assert_eq!(parse_version("no version here"), None);
+    }
+
+    #[test]
+    pub fn test_is_version_older() {
+        assert!(is_version_older("0.11.9", "0.12.0"));
+        assert!(is_version_older("2.9", "2.28.0"));
+        assert!(!is_version_older("2.28", "2.28.0"));
+        assert!(!is_version_older("9.6", "8.0"));
+        assert!(!is_version_older("3.2.7", "3.1.0"));
+    }
+}
@@ -0,0 +1,215 @@
+// dry_run_mod.rs
+
+//! Global `--dry-run` flag for all automation tasks.
+//!
+//! With `--dry-run` the shell commands are only printed fully sanitized and not executed.
+//! The requests to the GitHub API are only printed and the secret token is not even decrypted.
+//! Functions of cargo_auto_lib that change files are skipped with a message.
+//! Commands that only read, like `git status` inside cargo_auto_lib, are not affected.
+//!
+//! The functions and the ShellCommand struct have the same names and API as in cargo_auto_lib,
+//! so the tasks code looks the same with or without dry-run.
+
+use crate::cl;
+
+use cl::ShellCommandLimitedDoubleQuotesSanitizerTrait;
+#[allow(unused_imports)]
+use cl::{BLUE, GREEN, RED, RESET, YELLOW};
+
+/// The flag is set only once in the main() function.
+static DRY_RUN: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Remove the `--dry-run` argument from the arguments and set the global flag.
+pub fn extract_dry_run_argument(args: impl Iterator<Item = String>) -> Vec<String> {
+    args.filter(|arg| {
+        if arg == "--dry-run" {
+            DRY_RUN.store(true, std::sync::atomic::Ordering::Relaxed);
+            println!("  {BLUE}Dry-run: the commands are only printed, not executed.{RESET}");
+            false
+        } else {
+            true
+        }
+    })
+    .collect()
+}
+
+/// true if the `--dry-run` argument was used
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/// In dry-run print the planned action and return true, so the caller skips it.
+pub fn skip(planned_action: &str) -> bool {
+    if is_dry_run() {
+        println!("  {BLUE}[dry-run] {planned_action}{RESET}");
+    }
+    is_dry_run()
+}
+
+/// Like cl::run_shell_command_static, but only prints the command in dry-run.
+pub fn run_shell_command_static(shell_command: &'static str) -> cl::ResultWithLibError<()> {
+    run_shell_command(shell_command)
+}
+
+/// Like cl::run_shell_command, but only prints the command in dry-run.
+pub fn run_shell_command(shell_command: &str) -> cl::ResultWithLibError<()> {
+    if skip(&format!("$ {shell_command}")) {
+        return Ok(());
+    }
+    cl::run_shell_command(shell_command)
+}
+
+/// Like cl::auto_version_increment_semver_or_date, but skipped in dry-run.
+pub fn auto_version_increment_semver_or_date() {
+    if !skip("increment version in Cargo.toml") {
+        cl::auto_version_increment_semver_or_date();
+    }
+}
+
+/// Like cl::auto_cargo_toml_to_md, but skipped in dry-run.
+pub fn auto_cargo_toml_to_md() {
+    if !skip("write Cargo.toml data into md files") {
+        cl::auto_cargo_toml_to_md();
+    }
+}
+
+/// Like cl::auto_lines_of_code, but skipped in dry-run.
+pub fn auto_lines_of_code(link: &str) {
+    if !skip("write lines of code into README.md") {
+        cl::auto_lines_of_code(link);
+    }
+}
+
+/// Like cl::auto_plantuml, but skipped in dry-run.
+pub fn auto_plantuml(repo_url: &str) {
+    if !skip("render plantuml diagrams in md files") {
+        cl::auto_plantuml(repo_url);
+    }
+}
+
+/// Like cl::auto_playground_run_code, but skipped in dry-run.
+pub fn auto_playground_run_code() {
+    if !skip("write playground links in md files") {
+        cl::auto_playground_run_code();
+    }
+}
+
+/// Like cl::auto_md_to_doc_comments, but skipped in dry-run.
+pub fn auto_md_to_doc_comments() {
+    if !skip("copy md segments into doc comments") {
+        cl::auto_md_to_doc_comments();
+    }
+}
+
+/// Like cl::auto_doc_tidy_html, but skipped in dry-run.
+pub fn auto_doc_tidy_html() -> cl::ResultWithLibError<()> {
+    if skip("tidy html files in docs/") {
+        return Ok(());
+    }
+    cl::auto_doc_tidy_html()
+}
+
+/// Like cl::git_tag_sync_check_create_push, but skipped in dry-run.
+pub fn git_tag_sync_check_create_push(version: &str) -> String {
+    if skip(&format!("create and push git tag v{version} if it does not exist")) {
+        return format!("v{version}");
+    }
+    cl::git_tag_sync_check_create_push(version)
+}
+
+/// Like cl::new_local_repository, but skipped in dry-run.
+pub fn new_local_repository(message: &str) -> Option<()> {
+    if skip(&format!("create new local git repository with first commit \"{message}\"")) {
+        return Some(());
+    }
+    cl::new_local_repository(message)
+}
+
+/// Like cl::add_message_to_unreleased, but skipped in dry-run.
+pub fn add_message_to_unreleased(message: &str) {
+    if !skip(&format!("add \"{message}\" to the Unreleased section of RELEASES.md")) {
+        cl::add_message_to_unreleased(message);
+    }
+}
+
+/// Like cl::create_new_version_in_releases_md, but skipped in dry-run.
+pub fn create_new_version_in_releases_md(release_name: &str) -> Option<()> {
+    if skip(&format!("create new version {release_name} in RELEASES.md")) {
+        return Some(());
+    }
+    cl::create_new_version_in_releases_md(release_name)
+}
+
+/// Print the planned GitHub API request instead of sending it.
+/// The returned dry-run response has the fields that the tasks read from the real responses.
+pub fn github_api_request(method: &str, url: &str, body: Option<&[u8]>) -> serde_json::Value {
+    println!("  {BLUE}[dry-run] GitHub API request: {method} {url}{RESET}");
+    if let Some(body) = body {
+        println!("  {BLUE}{}{RESET}", String::from_utf8_lossy(body));
+    }
+    // return
+    serde_json::json!({
+        "id": 0,
+        "login": "dry-run",
+        "name": "dry-run",
+        "description": "dry-run",
+        "html_url": "https://github.com/dry-run",
+        "ssh_url": "git@github.com:dry-run/dry-run.git",
+        "topics": [],
+    })
+}
+
+/// Shell command builder like cl::ShellCommandLimitedDoubleQuotesSanitizer that respects dry-run.
+///
+/// The sanitization is done by the inner cargo_auto_lib struct.
+/// Here we only keep a copy of the string to echo, because the inner one is private.
+pub struct ShellCommand {
+    inner: cl::ShellCommandLimitedDoubleQuotesSanitizer,
+    string_to_echo: String,
+}
+
+impl ShellCommandLimitedDoubleQuotesSanitizerTrait for ShellCommand {
+    fn new(template: &str) -> cl::ResultWithLibError<Self> {
+        Ok(ShellCommand {
+            inner: cl::ShellCommandLimitedDoubleQuotesSanitizer::new(template)?,
+            string_to_echo: template.to_string(),
+        })
+    }
+
+    fn arg(&mut self, placeholder: &str, value: &str) -> cl::ResultWithLibError<&mut Self> {
+        self.inner.arg(placeholder, value)?;
+        self.string_to_echo = self.string_to_echo.replace(placeholder, value);
+        Ok(self)
+    }
+
+    fn arg_secret(&mut self, placeholder: &str, value: &secrecy::SecretString) -> cl::ResultWithLibError<&mut Self> {
+        self.inner.arg_secret(placeholder, value)?;
+        // the secret is never echoed
+        self.string_to_echo = self.string_to_echo.replace(placeholder, "[REDACTED]");
+        Ok(self)
+    }
+
+    fn run(&self) -> cl::ResultWithLibError<()> {
+        if skip(&format!("$ {}", self.string_to_echo)) {
+            return Ok(());
+        }
+        self.inner.run()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_shell_command_string_to_echo() {
+        let mut shell_command = ShellCommand::new(r#"git commit -m "{message}" "#).unwrap();
+        shell_command.arg("{message}", "first commit").unwrap();
+        assert_eq!(shell_command.string_to_echo, r#"git commit -m "first commit" "#);
+        // the sanitizer of cargo_auto_lib still refuses double quotes in values
+        assert!(ShellCommand::new(r#"git commit -m "{message}" "#)
+            .unwrap()
+            .arg("{message}", r#"x" && rm -rf "y"#)
+            .is_err());
+    }
+}
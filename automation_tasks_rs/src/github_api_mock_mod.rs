@@ -0,0 +1,202 @@
+// github_api_mock_mod.rs
+
+//! Local mock server for the GitHub API, used only in tests.
+//!
+//! It listens on a random port of 127.0.0.1 and answers with canned json responses.
+//! All received requests are recorded, so the tests can check method, path, query and body.
+//! The base url environment variables of github_api_mod point to this server, like for GitHub Enterprise Server.
+//! The environment is the same for all threads, so only one mock server runs at a time.
+//! Only the standard library is used, like in serve_mod.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::github_api_mod as gh;
+
+/// A request as received by the mock server.
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub method: String,
+    /// path without the query
+    pub path: String,
+    pub query: String,
+    pub authorization: String,
+    pub body: Vec<u8>,
+}
+
+impl MockRequest {
+    /// The body parsed as json.
+    pub fn body_json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+}
+
+/// A canned response for a method and path.
+struct MockRoute {
+    method: String,
+    path: String,
+    status: u16,
+    body: String,
+}
+
+/// The tests with the mock server wait for each other, because they change the environment.
+static MOCK_SERVER_LOCK: Mutex<()> = Mutex::new(());
+
+/// The mock server fixture. Create it at the start of the test.
+pub struct GithubApiMock {
+    routes: Arc<Mutex<Vec<MockRoute>>>,
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+    /// Released after drop() restored the environment.
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl GithubApiMock {
+    /// Start the mock server and set the GitHub API and uploads base urls to it.
+    pub fn start() -> Self {
+        // a failed test must not block the other tests
+        let lock = MOCK_SERVER_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let routes: Arc<Mutex<Vec<MockRoute>>> = Arc::new(Mutex::new(Vec::new()));
+        let requests: Arc<Mutex<Vec<MockRequest>>> = Arc::new(Mutex::new(Vec::new()));
+
+        let thread_routes = routes.clone();
+        let thread_requests = requests.clone();
+        // the thread ends with the test process
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if let Err(err) = handle_connection(stream, &thread_routes, &thread_requests) {
+                    eprintln!("mock server connection error: {err}");
+                }
+            }
+        });
+
+        std::env::set_var(gh::GITHUB_API_BASE_URL_ENV, &base_url);
+        std::env::set_var(gh::GITHUB_UPLOADS_BASE_URL_ENV, &base_url);
+        GithubApiMock {
+            routes,
+            requests,
+            _lock: lock,
+        }
+    }
+
+    /// Answer requests to method and path with the status and the json body.
+    pub fn route(&self, method: &str, path: &str, status: u16, body: serde_json::Value) -> &Self {
+        self.routes.lock().unwrap().push(MockRoute {
+            method: method.to_string(),
+            path: path.to_string(),
+            status,
+            body: body.to_string(),
+        });
+        self
+    }
+
+    /// All requests received until now, in order.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// The received requests with this method and path.
+    pub fn requests_to(&self, method: &str, path: &str) -> Vec<MockRequest> {
+        self.requests()
+            .into_iter()
+            .filter(|request| request.method == method && request.path == path)
+            .collect()
+    }
+}
+
+impl Drop for GithubApiMock {
+    /// The next test must not send requests to a stopped mock server.
+    fn drop(&mut self) {
+        std::env::remove_var(gh::GITHUB_API_BASE_URL_ENV);
+        std::env::remove_var(gh::GITHUB_UPLOADS_BASE_URL_ENV);
+    }
+}
+
+/// Read one request, record it and write the canned response.
+/// Every response closes the connection, so there is no keep-alive to handle.
+fn handle_connection(mut stream: TcpStream, routes: &Mutex<Vec<MockRoute>>, requests: &Mutex<Vec<MockRequest>>) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default().to_string();
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path.to_string(), query.to_string()),
+        None => (target, String::new()),
+    };
+
+    let mut content_length = 0;
+    let mut is_chunked = false;
+    let mut authorization = String::new();
+    loop {
+        let mut header_line = String::new();
+        reader.read_line(&mut header_line)?;
+        let header_line = header_line.trim_end();
+        if header_line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header_line.split_once(':') {
+            let value = value.trim();
+            match name.to_lowercase().as_str() {
+                "content-length" => content_length = value.parse().unwrap_or(0),
+                "transfer-encoding" => is_chunked = value.eq_ignore_ascii_case("chunked"),
+                "authorization" => authorization = value.to_string(),
+                _ => {}
+            }
+        }
+    }
+    let body = if is_chunked {
+        read_chunked_body(&mut reader)?
+    } else {
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        body
+    };
+
+    let (status, response_body) = match routes
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|route| route.method == method && route.path == path)
+    {
+        Some(route) => (route.status, route.body.clone()),
+        // the same json as GitHub for unknown urls
+        None => (
+            404,
+            r#"{"message":"Not Found","documentation_url":"https://docs.github.com/rest"}"#.to_string(),
+        ),
+    };
+    requests.lock().unwrap().push(MockRequest {
+        method,
+        path,
+        query,
+        authorization,
+        body,
+    });
+
+    let response = format!(
+        "HTTP/1.1 {status} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response_body}",
+        response_body.len()
+    );
+    stream.write_all(response.as_bytes())?;
+    stream.flush()
+}
+
+/// Streamed bodies without Content-Length arrive in chunks: size in hex, data, until the zero size chunk.
+fn read_chunked_body(reader: &mut impl BufRead) -> std::io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let mut size_line = String::new();
+        reader.read_line(&mut size_line)?;
+        let size = usize::from_str_radix(size_line.trim(), 16).unwrap_or(0);
+        let mut chunk = vec![0; size + 2];
+        reader.read_exact(&mut chunk)?;
+        if size == 0 {
+            return Ok(body);
+        }
+        body.extend_from_slice(&chunk[..size]);
+    }
+}
@@ -0,0 +1,1002 @@
+// github_api_mod.rs
+
+//! GitHub API calls with dry-run, CI mode, a configurable base url and the mock server for tests.
+//!
+//! The modules cargo_auto_github_api_mod and github_api_token_with_oauth2_mod are updated
+//! with `cargo auto update_automation_tasks_rs`, so they are not changed here.
+//! Their request builders are used as they are and only the sending is wrapped.
+//! Every request goes to the base url from the environment variables GITHUB_API_BASE_URL and GITHUB_UPLOADS_BASE_URL,
+//! by default to GitHub. GitHub Enterprise Server has a different base url and the tests set the base url to the local mock server,
+//! which gets only a fake secret token.
+//! With `--dry-run` the request is only printed and the secret token is not even decrypted
+//! and with `--ci` the secret token comes from the environment instead of the file encrypted with the SSH key.
+//!
+//! The functions that create the repository, sync the topics and upload the release assets
+//! are copied from cargo_auto_github_api_mod and return errors, so they can be tested with the mock server.
+
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::cargo_auto_github_api_mod as cgl;
+use crate::ci_mode_mod as ci;
+use crate::cl;
+use crate::dry_run_mod as dr;
+use crate::encrypt_decrypt_with_ssh_key_mod::github_api_token_with_oauth2_mod as github_token;
+
+// traits must be in scope (Rust strangeness)
+use cl::CargoTomlPublicApiMethods;
+use cl::ShellCommandLimitedDoubleQuotesSanitizerTrait;
+#[allow(unused_imports)]
+use cl::{BLUE, GREEN, RED, RESET, YELLOW};
+
+/// The environment variable for the base url of the GitHub API, for GitHub Enterprise Server.
+pub const GITHUB_API_BASE_URL_ENV: &str = "GITHUB_API_BASE_URL";
+/// The environment variable for the base url of GitHub uploads, for GitHub Enterprise Server.
+pub const GITHUB_UPLOADS_BASE_URL_ENV: &str = "GITHUB_UPLOADS_BASE_URL";
+
+/// The request builders of cargo_auto_github_api_mod always use this base url.
+const GITHUB_API_DEFAULT_BASE_URL: &str = "https://api.github.com/";
+/// The default base url of GitHub uploads.
+const GITHUB_UPLOADS_DEFAULT_BASE_URL: &str = "https://uploads.github.com/";
+
+/// The fake secret token for the local mock server.
+const MOCK_SECRET_TOKEN: &str = "mock_secret_token";
+
+/// The base urls of the GitHub API and GitHub uploads.
+///
+/// Every request is sent to these base urls. The tests point them to the local mock server.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct GithubApiBaseUrl {
+    pub api: url::Url,
+    pub uploads: url::Url,
+}
+
+impl GithubApiBaseUrl {
+    /// Read the base urls from the environment variables. Without them the requests go to GitHub.
+    pub(crate) fn from_environment() -> anyhow::Result<Self> {
+        Ok(GithubApiBaseUrl {
+            api: base_url_from_environment(GITHUB_API_BASE_URL_ENV, GITHUB_API_DEFAULT_BASE_URL)?,
+            uploads: base_url_from_environment(GITHUB_UPLOADS_BASE_URL_ENV, GITHUB_UPLOADS_DEFAULT_BASE_URL)?,
+        })
+    }
+}
+
+/// The base url from the environment variable or the default.
+///
+/// The requests contain the secret token, so the base url must use https.
+/// Only the local mock server can use http.
+fn base_url_from_environment(env_var_name: &str, default_base_url: &str) -> anyhow::Result<url::Url> {
+    let mut base_url = match std::env::var(env_var_name) {
+        Ok(base_url) if !base_url.is_empty() => url::Url::parse(&base_url)
+            .map_err(|err| anyhow::anyhow!("The environment variable {env_var_name} is not a valid url: {base_url}. {err}"))?,
+        _ => url::Url::parse(default_base_url)?,
+    };
+    if base_url.scheme() != "https" && !is_local_mock_server(&base_url) {
+        anyhow::bail!("The environment variable {env_var_name} must be a https url: {base_url}");
+    }
+    // the paths of the requests are joined to the base url
+    if !base_url.path().ends_with('/') {
+        base_url.set_path(&format!("{}/", base_url.path()));
+    }
+    Ok(base_url)
+}
+
+/// The local mock server of the tests listens on http://127.0.0.1 and gets only a fake secret token.
+fn is_local_mock_server(base_url: &url::Url) -> bool {
+    base_url.scheme() == "http" && matches!(base_url.host_str(), Some("127.0.0.1") | Some("localhost"))
+}
+
+/// Send the request to GitHub API and return the json response.
+///
+/// Use it instead of github_api_token_with_oauth2_mod::send_to_github_api_with_secret_token().
+pub(crate) fn send_to_github_api(req: reqwest::blocking::RequestBuilder) -> anyhow::Result<serde_json::Value> {
+    let base_url = GithubApiBaseUrl::from_environment()?.api;
+    let mut req = req.build()?;
+    *req.url_mut() = url_with_base_url(req.url(), GITHUB_API_DEFAULT_BASE_URL, &base_url)?;
+    // with --dry-run only print the request, without the secret token
+    if dr::is_dry_run() {
+        return Ok(dr::github_api_request(
+            req.method().as_str(),
+            req.url().as_str(),
+            req.body().and_then(|body| body.as_bytes()),
+        ));
+    }
+    authorize_request(req.headers_mut(), &secret_token(&base_url)?)?;
+    let response_text = reqwest::blocking::Client::new().execute(req)?.text()?;
+    Ok(serde_json::from_str(&response_text)?)
+}
+
+/// Upload to GitHub uploads and return the json response.
+///
+/// Use it instead of github_api_token_with_oauth2_mod::upload_to_github_with_secret_token().
+async fn upload_to_github(req: reqwest::RequestBuilder) -> anyhow::Result<serde_json::Value> {
+    let base_url = GithubApiBaseUrl::from_environment()?.uploads;
+    let mut req = req.build()?;
+    *req.url_mut() = url_with_base_url(req.url(), GITHUB_UPLOADS_DEFAULT_BASE_URL, &base_url)?;
+    authorize_request(req.headers_mut(), &secret_token(&base_url)?)?;
+    let response_text = reqwest::Client::new().execute(req).await?.text().await?;
+    Ok(serde_json::from_str(&response_text)?)
+}
+
+/// Move the request from the default base url of the request builders to the configured base url.
+///
+/// The request contains the secret token, so it must go always and only to the configured base url
+/// and never to some other malicious url.
+fn url_with_base_url(url: &url::Url, default_base_url: &str, base_url: &url::Url) -> anyhow::Result<url::Url> {
+    let Some(relative_url) = url
+        .as_str()
+        .strip_prefix(default_base_url)
+        .or_else(|| url.as_str().strip_prefix(base_url.as_str()))
+    else {
+        anyhow::bail!("Url is not correct: {url}. It must be always {base_url}.");
+    };
+    let url_with_base_url = base_url.join(relative_url)?;
+    if !url_with_base_url.as_str().starts_with(base_url.as_str()) {
+        anyhow::bail!("Url is not correct: {url_with_base_url}. It must be always {base_url}.");
+    }
+    Ok(url_with_base_url)
+}
+
+/// The fake secret token for the local mock server, the secret token from the environment in CI mode
+/// or else the secret token encrypted with the SSH key.
+fn secret_token(base_url: &url::Url) -> anyhow::Result<SecretString> {
+    if is_local_mock_server(base_url) {
+        return Ok(SecretString::from(MOCK_SECRET_TOKEN));
+    }
+    if ci::is_ci_mode() {
+        return ci::secret_token_from_environment(ci::GITHUB_TOKEN_ENV);
+    }
+    github_token::get_github_secret_token()
+}
+
+/// Add the secret token to the request as a sensitive header, so it is never printed.
+fn authorize_request(headers: &mut reqwest::header::HeaderMap, secret_token: &SecretString) -> anyhow::Result<()> {
+    let mut authorization = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", secret_token.expose_secret()))?;
+    authorization.set_sensitive(true);
+    headers.insert(reqwest::header::AUTHORIZATION, authorization);
+    Ok(())
+}
+
+/// GitHub API returns errors as json with a "message" and sometimes a list of "errors".
+///
+/// Returns an error with all the messages and codes, so the caller can decide to panic or not.
+pub(crate) fn error_from_github_json(json_value: &serde_json::Value, api_name: &str) -> anyhow::Result<()> {
+    let Some(error_message) = json_value.get("message") else {
+        return Ok(());
+    };
+    let mut error_text = format!("Call to GitHub API {api_name} returned an error: {error_message}");
+    if let Some(errors) = json_value.get("errors").and_then(|errors| errors.as_array()) {
+        for error in errors.iter() {
+            if let Some(message) = error.get("message") {
+                error_text.push_str(&format!("\n{message}"));
+            } else if let Some(code) = error.get("code") {
+                error_text.push_str(&format!("\n{code}"));
+            }
+        }
+    }
+    anyhow::bail!(error_text)
+}
+
+/// The id of the new release from the json response of github_api_create_new_release().
+pub(crate) fn release_id_from_json(json_value: &serde_json::Value) -> anyhow::Result<String> {
+    error_from_github_json(json_value, "github_api_create_new_release")?;
+    let Some(release_id) = json_value.get("id").and_then(|id| id.as_i64()) else {
+        anyhow::bail!("The response of GitHub API github_api_create_new_release has no release id.");
+    };
+    Ok(release_id.to_string())
+}
+
+/// The GitHub release for the tag, if a failed github_new_release already created it.
+pub(crate) struct ExistingRelease {
+    pub release_id: String,
+    /// The names of the assets already uploaded to the release.
+    pub asset_names: Vec<String>,
+}
+
+/// Get the release for the tag, so a failed github_new_release can be resumed without creating a second release.
+///
+/// None if the release does not exist yet. In dry-run there is no real release.
+pub(crate) fn existing_release_for_tag(
+    github_owner_or_organization: &str,
+    repo_name: &str,
+    tag_name: &str,
+) -> anyhow::Result<Option<ExistingRelease>> {
+    let json_value = send_to_github_api(github_api_get_release_by_tag(github_owner_or_organization, repo_name, tag_name)?)?;
+    if dr::is_dry_run() || json_value.get("message").and_then(|message| message.as_str()) == Some("Not Found") {
+        return Ok(None);
+    }
+    error_from_github_json(&json_value, "github_api_get_release_by_tag")?;
+    let Some(release_id) = json_value.get("id").and_then(|id| id.as_i64()) else {
+        anyhow::bail!("The response of GitHub API github_api_get_release_by_tag has no release id.");
+    };
+    let asset_names = json_value
+        .get("assets")
+        .and_then(|assets| assets.as_array())
+        .map(|assets| {
+            assets
+                .iter()
+                .filter_map(|asset| asset.get("name").and_then(|name| name.as_str()).map(|name| name.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(Some(ExistingRelease {
+        release_id: release_id.to_string(),
+        asset_names,
+    }))
+}
+
+/// GitHub api get a release by tag name, like the request builders in cargo_auto_github_api_mod
+fn github_api_get_release_by_tag(
+    github_owner_or_organization: &str,
+    repo_name: &str,
+    tag_name: &str,
+) -> anyhow::Result<reqwest::blocking::RequestBuilder> {
+    /*
+        https://docs.github.com/en/rest/releases/releases?apiVersion=2022-11-28#get-a-release-by-tag-name
+
+        curl -L \
+        -H "Accept: application/vnd.github+json" \
+        -H "Authorization: Bearer <YOUR-TOKEN>" \
+        -H "X-GitHub-Api-Version: 2022-11-28" \
+        https://api.github.com/repos/OWNER/REPO/releases/tags/TAG
+    */
+    let base_url = GithubApiBaseUrl::from_environment()?.api;
+    let releases_url = base_url.join(&format!(
+        "repos/{github_owner_or_organization}/{repo_name}/releases/tags/{tag_name}"
+    ))?;
+    // return
+    Ok(reqwest::blocking::Client::new()
+        .get(releases_url.as_str())
+        .header("Accept", "application/vnd.github+json")
+        .header("X-GitHub-Api-Version", "2022-11-28")
+        .header("User-Agent", "cargo_auto_lib"))
+}
+
+/// The data from Cargo.toml for the remote GitHub repository.
+pub(crate) struct RepositoryMetadata {
+    pub github_owner_or_organization: String,
+    pub package_name: String,
+    pub description: String,
+    pub keywords: Vec<String>,
+}
+
+impl RepositoryMetadata {
+    /// Read Cargo.toml. The repository must contain the real github_owner.
+    pub(crate) fn read_cargo_toml() -> anyhow::Result<Self> {
+        let cargo_toml = cl::CargoToml::read();
+        // the second fragment of URL can be the github_owner (authenticated_user) or organization
+        let Some(github_owner_or_organization) = cargo_toml.github_owner() else {
+            anyhow::bail!("ERROR: Element Repository in Cargo.toml does not contain the github_owner!");
+        };
+        if github_owner_or_organization == "github_owner" {
+            anyhow::bail!(
+                "Error: The placeholder 'github_owner' in Cargo.toml/repository is not changed to the real github_owner or GitHub Organization."
+            );
+        }
+        let Some(description) = cargo_toml.package_description() else {
+            anyhow::bail!("ERROR: Element Description in Cargo.toml does not exist!");
+        };
+        Ok(RepositoryMetadata {
+            github_owner_or_organization,
+            package_name: cargo_toml.package_name(),
+            description,
+            keywords: cargo_toml.package_keywords(),
+        })
+    }
+}
+
+/// Interactive ask to create a new remote GitHub repository.
+pub(crate) fn new_remote_github_repository() -> Option<()> {
+    // early error if Repository contains the placeholder "github_owner" or does not contain the true github_owner
+    let repository_metadata = RepositoryMetadata::read_cargo_toml().unwrap_or_else(|e| panic!("{RED}{e}{RESET}"));
+    new_remote_github_repository_in_folder(
+        camino::Utf8Path::new("."),
+        &repository_metadata,
+        confirm_new_remote_github_repository,
+    )
+    .unwrap_or_else(|e| panic!("{RED}{e}{RESET}"))
+}
+
+/// Ask to create the new remote GitHub repository. With --dry-run don't ask, just show the planned requests.
+fn confirm_new_remote_github_repository() -> anyhow::Result<bool> {
+    if dr::is_dry_run() {
+        return Ok(true);
+    }
+    ci::error_if_ci_mode("Do you want to create a new remote GitHub repository?")?;
+    let answer = inquire::Text::new(&format!("{BLUE}Do you want to create a new remote GitHub repository? (y/n){RESET}")).prompt()?;
+    Ok(answer.to_lowercase() == "y")
+}
+
+/// Create the remote GitHub repository for the git repository in the project folder, then push and create the pages site.
+///
+/// Returns None if the user does not want a new remote repository.
+fn new_remote_github_repository_in_folder(
+    project_folder: &camino::Utf8Path,
+    repository_metadata: &RepositoryMetadata,
+    confirm: fn() -> anyhow::Result<bool>,
+) -> anyhow::Result<Option<()>> {
+    let github_owner_or_organization = &repository_metadata.github_owner_or_organization;
+    let package_name = &repository_metadata.package_name;
+    let is_user_repository = github_owner_is_authenticated_user(github_owner_or_organization)?;
+
+    if !git_has_remote(project_folder)? {
+        println!("{BLUE}This project does not have a remote GitHub repository.{RESET}");
+        if !confirm()? {
+            // early exit
+            return Ok(None);
+        }
+        let json_value = create_remote_github_repository(
+            github_owner_or_organization,
+            is_user_repository,
+            package_name,
+            &repository_metadata.description,
+        )?;
+
+        // get just the name, description and html_url from json
+        let json_str = |name: &str| {
+            json_value
+                .get(name)
+                .and_then(|value| value.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        println!("  {YELLOW}name: {}{RESET}", json_str("name"));
+        println!("  {YELLOW}description: {}{RESET}", json_str("description"));
+        println!("  {YELLOW}url: {}{RESET}", json_str("html_url"));
+
+        // add this GitHub repository to origin remote over SSH (use sshadd for passphrase)
+        // the ssh_url from the response is correct also for GitHub Enterprise Server
+        let ssh_url = json_str("ssh_url");
+        if ssh_url.is_empty() {
+            anyhow::bail!("The response of GitHub API github_api_user_repository_new has no ssh_url.");
+        }
+        dr::ShellCommand::new(r#"git -C "{project_folder}" remote add origin "{ssh_url}" "#)?
+            .arg("{project_folder}", project_folder.as_str())?
+            .arg("{ssh_url}", &ssh_url)?
+            .run()?;
+    }
+
+    if !git_has_upstream(project_folder)? {
+        dr::ShellCommand::new(r#"git -C "{project_folder}" push -u origin main "#)?
+            .arg("{project_folder}", project_folder.as_str())?
+            .run()?;
+
+        // the docs pages are created with a GitHub action
+        let _json = send_to_github_api(cgl::github_api_create_a_github_pages_site(
+            github_owner_or_organization,
+            package_name,
+        ));
+    }
+
+    Ok(Some(()))
+}
+
+/// Has git remote, like cargo_auto_github_api_mod::git_has_remote() for the project folder.
+fn git_has_remote(project_folder: &camino::Utf8Path) -> anyhow::Result<bool> {
+    // git remote returns only "origin" if exists or nothing if it does not exist
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(project_folder)
+        .arg("remote")
+        .output()?;
+    Ok(!output.stdout.is_empty())
+}
+
+/// Has git upstream, like cargo_auto_github_api_mod::git_has_upstream() for the project folder.
+fn git_has_upstream(project_folder: &camino::Utf8Path) -> anyhow::Result<bool> {
+    // git branch -vv returns upstream branches in angle brackets []
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(project_folder)
+        .args(["branch", "-vv"])
+        .output()?;
+    Ok(String::from_utf8_lossy(&output.stdout).contains('['))
+}
+
+/// Is the github_owner the authenticated user of the secret_token or a GitHub Organization?
+///
+/// Returns true for a User Repository and false for an Organization Repository.
+pub(crate) fn github_owner_is_authenticated_user(github_owner_or_organization: &str) -> anyhow::Result<bool> {
+    // get authenticated user from Github
+    let json_value = send_to_github_api(cgl::github_api_get_authenticated_user())?;
+    let Some(authenticated_user_login) = json_value.get("login").and_then(|login| login.as_str()) else {
+        anyhow::bail!("ERROR: Unrecognized Authenticated on GitHub from secret_token.");
+    };
+    if github_owner_or_organization == authenticated_user_login {
+        // this repository is a User Repository
+        return Ok(true);
+    }
+    // check if it is a GitHub Organization
+    let json_value = send_to_github_api(cgl::github_api_get_organization(github_owner_or_organization))?;
+    if json_value.get("login").is_none() {
+        anyhow::bail!("ERROR: Unrecognized Organization on GitHub: {github_owner_or_organization}.");
+    }
+    Ok(false)
+}
+
+/// Create the new remote User or Organization repository and return the json of the new repository.
+pub(crate) fn create_remote_github_repository(
+    github_owner_or_organization: &str,
+    is_user_repository: bool,
+    package_name: &str,
+    description: &str,
+) -> anyhow::Result<serde_json::Value> {
+    let json_value = if is_user_repository {
+        // new User repository
+        let json_value = send_to_github_api(cgl::github_api_user_repository_new(
+            github_owner_or_organization,
+            package_name,
+            description,
+        ))?;
+        error_from_github_json(&json_value, "github_api_user_repository_new")?;
+        json_value
+    } else {
+        // new Organization repository
+        let json_value = send_to_github_api(cgl::github_api_organization_repository_new(
+            github_owner_or_organization,
+            package_name,
+            description,
+        ))?;
+        error_from_github_json(&json_value, "github_api_organization_repository_new")?;
+        json_value
+    };
+    Ok(json_value)
+}
+
+/// Check and modify the description and topics on Github
+///
+/// Copied from cargo_auto_github_api_mod with dry-run.
+///
+/// The words topics, keywords and tags all mean the same concept.
+/// In cargo.toml we have keywords.
+/// In README.md I want to have badges for tags
+/// In GitHub they are topics.
+/// Topic must be only one word: lowercase letters, hyphens(-) or numbers, less then 35 characters.
+/// I want to avoid GitHub API at every git push. I will store the old description and topics
+/// in the file automation_tasks_rs/.old_metadata.json
+/// So I can compare first locally and only when they differ call the Github API.
+pub(crate) fn description_and_topics_to_github() {
+    let repository_metadata = RepositoryMetadata::read_cargo_toml().unwrap_or_else(|e| panic!("{RED}{e}{RESET}"));
+    description_and_topics_to_github_in_folder(camino::Utf8Path::new("."), &repository_metadata)
+        .unwrap_or_else(|e| panic!("{RED}{e}{RESET}"));
+}
+
+/// Sync the description and topics, if they differ from automation_tasks_rs/.old_metadata.json in the project folder.
+fn description_and_topics_to_github_in_folder(
+    project_folder: &camino::Utf8Path,
+    repository_metadata: &RepositoryMetadata,
+) -> anyhow::Result<()> {
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct OldMetadata {
+        old_description: String,
+        old_keywords: Vec<String>,
+    }
+
+    // read data from automation_tasks_rs/.old_metadata.json
+    let old_metadata_path = project_folder.join("automation_tasks_rs/.old_metadata.json");
+    let mut is_old_metadata_different = true;
+    if let Ok(old_metadata) = std::fs::read_to_string(&old_metadata_path) {
+        if let Ok(old_metadata) = serde_json::from_str::<OldMetadata>(&old_metadata) {
+            if old_metadata.old_description == repository_metadata.description && old_metadata.old_keywords == repository_metadata.keywords
+            {
+                is_old_metadata_different = false;
+            }
+        }
+    }
+
+    if is_old_metadata_different {
+        let is_topics_replaced = sync_description_and_topics_to_github(
+            &repository_metadata.github_owner_or_organization,
+            &repository_metadata.package_name,
+            &repository_metadata.description,
+            &repository_metadata.keywords,
+        )?;
+        if is_topics_replaced {
+            // write into automation_tasks_rs/.old_metadata.json file
+            let old_metadata = OldMetadata {
+                old_description: repository_metadata.description.clone(),
+                old_keywords: repository_metadata.keywords.clone(),
+            };
+            if !dr::skip(&format!("write {old_metadata_path}")) {
+                std::fs::write(&old_metadata_path, serde_json::to_string_pretty(&old_metadata)?)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Compare the description and topics on GitHub with the local ones and update them if they differ.
+///
+/// Returns true if the topics were replaced.
+pub(crate) fn sync_description_and_topics_to_github(
+    github_owner_or_organization: &str,
+    repo_name: &str,
+    description: &str,
+    keywords: &Vec<String>,
+) -> anyhow::Result<bool> {
+    // get data from GitHub
+    let json = send_to_github_api(cgl::github_api_get_repository(github_owner_or_organization, repo_name))?;
+    error_from_github_json(&json, "github_api_get_repository")?;
+
+    // get just the description and topis from json
+    // a new repository without description has null
+    let gh_description = json.get("description").and_then(|value| value.as_str()).unwrap_or_default();
+    let gh_topics: Vec<String> = json
+        .get("topics")
+        .and_then(|value| value.as_array())
+        .map(|topics| {
+            topics
+                .iter()
+                .filter_map(|value| value.as_str())
+                .map(|value| value.to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // are description and topics both equal?
+    if gh_description != description {
+        let json = send_to_github_api(cgl::github_api_update_description(
+            github_owner_or_organization,
+            repo_name,
+            description,
+        ))?;
+        error_from_github_json(&json, "github_api_update_description")?;
+    }
+
+    // all elements must be equal, but not necessary in the same order
+    let topics_is_equal = if gh_topics.len() == keywords.len() {
+        let mut elements_is_equal = true;
+        'outer: for x in gh_topics.iter() {
+            let mut has_element = false;
+            'inner: for y in keywords.iter() {
+                if y == x {
+                    has_element = true;
+                    break 'inner;
+                }
+            }
+            if !has_element {
+                elements_is_equal = false;
+                break 'outer;
+            }
+        }
+        elements_is_equal
+    } else {
+        false
+    };
+
+    if !topics_is_equal {
+        let json = send_to_github_api(cgl::github_api_replace_all_topics(
+            github_owner_or_organization,
+            repo_name,
+            keywords,
+        ))?;
+        error_from_github_json(&json, "github_api_replace_all_topics")?;
+    }
+    Ok(!topics_is_equal)
+}
+
+/// Upload asset to github release
+///
+/// Copied from cargo_auto_github_api_mod with dry-run, returns the error json from GitHub uploads as error.
+pub(crate) fn github_api_upload_asset_to_release(
+    github_owner_or_organization: &str,
+    repo: &str,
+    release_id: &str,
+    path_to_file: &str,
+) -> anyhow::Result<()> {
+    println!("  {YELLOW}Uploading file to GitHub release: {path_to_file}{RESET}");
+    let file = camino::Utf8Path::new(&path_to_file);
+    let file_name = file.file_name().unwrap();
+
+    let base_url = GithubApiBaseUrl::from_environment()?.uploads;
+    let mut release_upload_url = base_url.join(&format!("repos/{github_owner_or_organization}/{repo}/releases/{release_id}/assets"))?;
+    release_upload_url.set_query(Some(format!("{}={}", "name", file_name).as_str()));
+    if dr::skip(&format!("GitHub upload request: POST {release_upload_url}")) {
+        return Ok(());
+    }
+    let file_size = std::fs::metadata(file)?.len();
+    println!("  {YELLOW}It can take some time to upload. File size: {file_size}. Wait...{RESET}");
+    // region: async code made sync locally
+    let rt = tokio::runtime::Runtime::new()?;
+    let json_value = rt.block_on(async move {
+        let file = tokio::fs::File::open(file).await?;
+        let stream = tokio_util::codec::FramedRead::new(file, tokio_util::codec::BytesCodec::new());
+        let body = reqwest::Body::wrap_stream(stream);
+
+        let req = reqwest::Client::new()
+            .post(release_upload_url.as_str())
+            .header("Content-Type", "application/octet-stream")
+            .header("Content-Length", file_size.to_string())
+            .body(body);
+
+        upload_to_github(req).await
+    })?;
+    // endregion: async code made sync locally
+    error_from_github_json(&json_value, "github_api_upload_asset_to_release")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::github_api_mock_mod::GithubApiMock;
+
+    #[test]
+    pub fn test_github_owner_is_authenticated_user_or_organization() {
+        let mock = GithubApiMock::start();
+        mock.route("GET", "/user", 200, serde_json::json!({"login": "bestia-dev", "id": 1}))
+            .route(
+                "GET",
+                "/orgs/automation-tasks-rs",
+                200,
+                serde_json::json!({"login": "automation-tasks-rs", "id": 2}),
+            );
+
+        assert!(github_owner_is_authenticated_user("bestia-dev").unwrap());
+        assert!(!github_owner_is_authenticated_user("automation-tasks-rs").unwrap());
+        // the mock answers unknown organizations with "Not Found"
+        let err = github_owner_is_authenticated_user("unknown-org").unwrap_err();
+        assert!(err.to_string().contains("Unrecognized Organization on GitHub: unknown-org"));
+        // the real secret_token is never sent to the mock server
+        assert!(mock
+            .requests()
+            .iter()
+            .all(|request| request.authorization == "Bearer mock_secret_token"));
+    }
+
+    #[test]
+    pub fn test_create_remote_github_repository() {
+        let mock = GithubApiMock::start();
+        mock.route(
+            "POST",
+            "/user/repos",
+            201,
+            serde_json::json!({"name": "snake", "description": "A game", "html_url": "https://github.com/bestia-dev/snake"}),
+        );
+
+        let json_value = create_remote_github_repository("bestia-dev", true, "snake", "A game").unwrap();
+        assert_eq!(json_value["html_url"], "https://github.com/bestia-dev/snake");
+        let request = &mock.requests_to("POST", "/user/repos")[0];
+        assert_eq!(request.body_json()["name"], "snake");
+        assert_eq!(request.body_json()["homepage"], "https://bestia-dev.github.io/snake");
+    }
+
+    #[test]
+    pub fn test_create_remote_github_repository_error_json() {
+        let mock = GithubApiMock::start();
+        mock.route(
+            "POST",
+            "/orgs/automation-tasks-rs/repos",
+            422,
+            serde_json::json!({
+                "message": "Repository creation failed.",
+                "errors": [{"resource": "Repository", "code": "custom", "field": "name", "message": "name already exists on this account"}]
+            }),
+        );
+
+        let err = create_remote_github_repository("automation-tasks-rs", false, "snake", "A game").unwrap_err();
+        let err = err.to_string();
+        assert!(err.contains("github_api_organization_repository_new"));
+        assert!(err.contains("Repository creation failed."));
+        assert!(err.contains("name already exists on this account"));
+    }
+
+    #[test]
+    pub fn test_sync_description_and_topics_to_github() {
+        let mock = GithubApiMock::start();
+        mock.route(
+            "GET",
+            "/repos/bestia-dev/snake",
+            200,
+            serde_json::json!({"description": "Old description", "topics": ["rustlang", "wasm"]}),
+        )
+        .route(
+            "PATCH",
+            "/repos/bestia-dev/snake",
+            200,
+            serde_json::json!({"description": "A game"}),
+        )
+        .route(
+            "PUT",
+            "/repos/bestia-dev/snake/topics",
+            200,
+            serde_json::json!({"names": ["wasm", "rustlang", "pwa"]}),
+        );
+
+        // topics in a different order are equal
+        let keywords = vec!["wasm".to_string(), "rustlang".to_string()];
+        assert!(!sync_description_and_topics_to_github("bestia-dev", "snake", "Old description", &keywords).unwrap());
+        assert_eq!(mock.requests().len(), 1);
+
+        let keywords = vec!["wasm".to_string(), "rustlang".to_string(), "pwa".to_string()];
+        assert!(sync_description_and_topics_to_github("bestia-dev", "snake", "A game", &keywords).unwrap());
+        assert_eq!(
+            mock.requests_to("PATCH", "/repos/bestia-dev/snake")[0].body_json(),
+            serde_json::json!({"description": "A game"})
+        );
+        assert_eq!(
+            mock.requests_to("PUT", "/repos/bestia-dev/snake/topics")[0].body_json(),
+            serde_json::json!({"names": keywords})
+        );
+    }
+
+    #[test]
+    pub fn test_sync_description_and_topics_to_github_error_json() {
+        let _mock = GithubApiMock::start();
+        // no routes: the mock returns "Not Found" for the repository
+        let err = sync_description_and_topics_to_github("bestia-dev", "snake", "A game", &vec![]).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("github_api_get_repository returned an error: \"Not Found\""));
+    }
+
+    #[test]
+    pub fn test_github_api_create_a_github_pages_site() {
+        let mock = GithubApiMock::start();
+        mock.route(
+            "POST",
+            "/repos/bestia-dev/snake/pages",
+            201,
+            serde_json::json!({"html_url": "https://bestia-dev.github.io/snake"}),
+        );
+
+        send_to_github_api(cgl::github_api_create_a_github_pages_site("bestia-dev", "snake")).unwrap();
+        let request = &mock.requests_to("POST", "/repos/bestia-dev/snake/pages")[0];
+        assert_eq!(request.body_json()["build_type"], "workflow");
+        assert_eq!(request.body_json()["source"]["path"], "/docs");
+    }
+
+    #[test]
+    pub fn test_github_api_create_new_release() {
+        let mock = GithubApiMock::start();
+        mock.route(
+            "POST",
+            "/repos/bestia-dev/snake/releases",
+            201,
+            serde_json::json!({"id": 42, "tag_name": "v1.0.0"}),
+        );
+
+        let request = cgl::github_api_create_new_release("bestia-dev", "snake", "v1.0.0", "Version 1.0.0", "main", "- new release");
+        let json_value = send_to_github_api(request).unwrap();
+        assert_eq!(release_id_from_json(&json_value).unwrap(), "42");
+        let body = mock.requests_to("POST", "/repos/bestia-dev/snake/releases")[0].body_json();
+        assert_eq!(body["tag_name"], "v1.0.0");
+        assert_eq!(body["target_commitish"], "main");
+        assert_eq!(body["body"], "- new release");
+        assert_eq!(body["draft"], false);
+    }
+
+    #[test]
+    pub fn test_github_api_create_new_release_error_json() {
+        let mock = GithubApiMock::start();
+        mock.route(
+            "POST",
+            "/repos/bestia-dev/snake/releases",
+            422,
+            serde_json::json!({
+                "message": "Validation Failed",
+                "errors": [{"resource": "Release", "code": "already_exists", "field": "tag_name"}]
+            }),
+        );
+
+        let request = cgl::github_api_create_new_release("bestia-dev", "snake", "v1.0.0", "Version 1.0.0", "main", "");
+        let json_value = send_to_github_api(request).unwrap();
+        let err = release_id_from_json(&json_value).unwrap_err().to_string();
+        assert!(err.contains("Validation Failed"));
+        assert!(err.contains("already_exists"));
+    }
+
+    #[test]
+    pub fn test_existing_release_for_tag() {
+        let mock = GithubApiMock::start();
+        mock.route(
+            "GET",
+            "/repos/bestia-dev/snake/releases/tags/v1.0.0",
+            200,
+            serde_json::json!({"id": 42, "tag_name": "v1.0.0", "assets": [{"id": 1, "name": "snake-v1.0.0-web.tar.gz"}]}),
+        )
+        .route(
+            "GET",
+            "/repos/bestia-dev/snake/releases/tags/v1.0.1",
+            404,
+            serde_json::json!({"message": "Not Found"}),
+        );
+
+        let existing_release = existing_release_for_tag("bestia-dev", "snake", "v1.0.0").unwrap().unwrap();
+        assert_eq!(existing_release.release_id, "42");
+        assert_eq!(existing_release.asset_names, vec!["snake-v1.0.0-web.tar.gz"]);
+        assert!(existing_release_for_tag("bestia-dev", "snake", "v1.0.1").unwrap().is_none());
+    }
+
+    #[test]
+    pub fn test_github_api_upload_asset_to_release() {
+        let mock = GithubApiMock::start();
+        mock.route(
+            "POST",
+            "/repos/bestia-dev/snake/releases/42/assets",
+            201,
+            serde_json::json!({"id": 1, "state": "uploaded"}),
+        );
+        let path_to_file = std::env::temp_dir().join(format!("snake-v1.0.0-web-SHA256SUMS-{}.txt", std::process::id()));
+        std::fs::write(&path_to_file, "checksums").unwrap();
+
+        github_api_upload_asset_to_release("bestia-dev", "snake", "42", path_to_file.to_str().unwrap()).unwrap();
+        let request = &mock.requests_to("POST", "/repos/bestia-dev/snake/releases/42/assets")[0];
+        assert_eq!(
+            request.query,
+            format!("name={}", path_to_file.file_name().unwrap().to_str().unwrap())
+        );
+        assert_eq!(request.body, b"checksums");
+        assert_eq!(request.authorization, "Bearer mock_secret_token");
+
+        // the error json from GitHub uploads is returned as error
+        let err = github_api_upload_asset_to_release("bestia-dev", "snake", "43", path_to_file.to_str().unwrap()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("github_api_upload_asset_to_release returned an error: \"Not Found\""));
+        std::fs::remove_file(&path_to_file).unwrap();
+    }
+
+    #[test]
+    pub fn test_url_with_base_url() {
+        let base_url = url::Url::parse("https://github.example.com/api/v3/").unwrap();
+        let url = url::Url::parse("https://api.github.com/repos/bestia-dev/snake/topics?per_page=1").unwrap();
+        assert_eq!(
+            url_with_base_url(&url, GITHUB_API_DEFAULT_BASE_URL, &base_url).unwrap().as_str(),
+            "https://github.example.com/api/v3/repos/bestia-dev/snake/topics?per_page=1"
+        );
+        // the request builders of github_api_mod already use the base url
+        let url = url::Url::parse("https://github.example.com/api/v3/user").unwrap();
+        assert_eq!(
+            url_with_base_url(&url, GITHUB_API_DEFAULT_BASE_URL, &base_url).unwrap().as_str(),
+            "https://github.example.com/api/v3/user"
+        );
+        // the secret token must never go to another host
+        for url in ["https://example.com/user", "https://api.github.com///example.com/user"] {
+            let url = url::Url::parse(url).unwrap();
+            assert!(url_with_base_url(&url, GITHUB_API_DEFAULT_BASE_URL, &base_url).is_err());
+        }
+    }
+
+    #[test]
+    pub fn test_base_url_from_environment() {
+        let env_var_name = "TEST_GITHUB_API_BASE_URL";
+        assert_eq!(
+            base_url_from_environment(env_var_name, GITHUB_API_DEFAULT_BASE_URL)
+                .unwrap()
+                .as_str(),
+            "https://api.github.com/"
+        );
+        std::env::set_var(env_var_name, "https://github.example.com/api/v3");
+        assert_eq!(
+            base_url_from_environment(env_var_name, GITHUB_API_DEFAULT_BASE_URL)
+                .unwrap()
+                .as_str(),
+            "https://github.example.com/api/v3/"
+        );
+        // only the local mock server can use http
+        std::env::set_var(env_var_name, "http://github.example.com/api/v3");
+        let err = base_url_from_environment(env_var_name, GITHUB_API_DEFAULT_BASE_URL).unwrap_err();
+        assert!(err.to_string().contains("must be a https url"));
+        std::env::remove_var(env_var_name);
+    }
+
+    /// A new git repository with one commit on main and an empty bare repository as the future remote.
+    fn git_project_folder(test_name: &str) -> camino::Utf8PathBuf {
+        let folder = camino::Utf8PathBuf::try_from(std::env::temp_dir())
+            .unwrap()
+            .join(format!("{test_name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git").args(args).current_dir(&folder).status().unwrap();
+            assert!(status.success(), "git {args:?}");
+        };
+        git(&["init", "--quiet", "--bare", "remote.git"]);
+        git(&["init", "--quiet", "-b", "main", "project"]);
+        git(&[
+            "-C",
+            "project",
+            "-c",
+            "user.name=test",
+            "-c",
+            "user.email=test@example.com",
+            "commit",
+            "--quiet",
+            "--allow-empty",
+            "-m",
+            "init",
+        ]);
+        folder
+    }
+
+    #[test]
+    pub fn test_new_remote_github_repository_in_folder() {
+        let folder = git_project_folder("test_new_remote_github_repository_in_folder");
+        let project_folder = folder.join("project");
+        let repository_metadata = RepositoryMetadata {
+            github_owner_or_organization: "bestia-dev".to_string(),
+            package_name: "snake".to_string(),
+            description: "A game".to_string(),
+            keywords: vec![],
+        };
+        let mock = GithubApiMock::start();
+        mock.route("GET", "/user", 200, serde_json::json!({"login": "bestia-dev", "id": 1}))
+            .route(
+                "POST",
+                "/user/repos",
+                201,
+                serde_json::json!({"name": "snake", "description": "A game", "html_url": "https://github.com/bestia-dev/snake", "ssh_url": folder.join("remote.git").as_str()}),
+            )
+            .route("POST", "/repos/bestia-dev/snake/pages", 201, serde_json::json!({}));
+
+        // the user does not want a new remote repository
+        assert!(
+            new_remote_github_repository_in_folder(&project_folder, &repository_metadata, || Ok(false))
+                .unwrap()
+                .is_none()
+        );
+        assert!(mock.requests_to("POST", "/user/repos").is_empty());
+
+        assert!(
+            new_remote_github_repository_in_folder(&project_folder, &repository_metadata, || Ok(true))
+                .unwrap()
+                .is_some()
+        );
+        assert_eq!(mock.requests_to("POST", "/user/repos")[0].body_json()["name"], "snake");
+        assert_eq!(mock.requests_to("POST", "/repos/bestia-dev/snake/pages").len(), 1);
+        // the remote is added and main is pushed with upstream
+        assert!(git_has_remote(&project_folder).unwrap());
+        assert!(git_has_upstream(&project_folder).unwrap());
+
+        // nothing to do the second time
+        let requests_count = mock.requests().len();
+        new_remote_github_repository_in_folder(&project_folder, &repository_metadata, || panic!("no question"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(mock.requests().len(), requests_count + 1);
+        assert_eq!(mock.requests().last().unwrap().path, "/user");
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    pub fn test_description_and_topics_to_github_in_folder() {
+        let project_folder = camino::Utf8PathBuf::try_from(std::env::temp_dir())
+            .unwrap()
+            .join(format!("test_description_and_topics_to_github_in_folder_{}", std::process::id()));
+        std::fs::create_dir_all(project_folder.join("automation_tasks_rs")).unwrap();
+        let repository_metadata = RepositoryMetadata {
+            github_owner_or_organization: "bestia-dev".to_string(),
+            package_name: "snake".to_string(),
+            description: "A game".to_string(),
+            keywords: vec!["wasm".to_string(), "pwa".to_string()],
+        };
+        let mock = GithubApiMock::start();
+        mock.route(
+            "GET",
+            "/repos/bestia-dev/snake",
+            200,
+            serde_json::json!({"description": "Old description", "topics": ["wasm"]}),
+        )
+        .route(
+            "PATCH",
+            "/repos/bestia-dev/snake",
+            200,
+            serde_json::json!({"description": "A game"}),
+        )
+        .route(
+            "PUT",
+            "/repos/bestia-dev/snake/topics",
+            200,
+            serde_json::json!({"names": ["wasm", "pwa"]}),
+        );
+
+        description_and_topics_to_github_in_folder(&project_folder, &repository_metadata).unwrap();
+        assert_eq!(mock.requests().len(), 3);
+        let old_metadata = std::fs::read_to_string(project_folder.join("automation_tasks_rs/.old_metadata.json")).unwrap();
+        assert!(old_metadata.contains("A game"));
+
+        // the same metadata as in .old_metadata.json does not call GitHub API
+        description_and_topics_to_github_in_folder(&project_folder, &repository_metadata).unwrap();
+        assert_eq!(mock.requests().len(), 3);
+        std::fs::remove_dir_all(&project_folder).unwrap();
+    }
+}
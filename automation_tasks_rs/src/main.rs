@@ -4,23 +4,38 @@
 
 mod build_cli_bin_mod;
 mod build_lib_mod;
-mod build_wasm_mod;
 mod cargo_auto_github_api_mod;
+mod ci_mode_mod;
+mod deploy_mod;
+mod doctor_mod;
+mod dry_run_mod;
 mod encrypt_decrypt_with_ssh_key_mod;
 mod generic_functions_mod;
-mod tasks_mod;
+#[cfg(test)]
+mod github_api_mock_mod;
+mod github_api_mod;
+mod rotate_secret_key_mod;
+mod secrets_mod;
+mod release_assets_mod;
+mod serve_mod;
+mod ship_mod;
+mod update_automation_tasks_rs_mod;
+mod wasm_size_mod;
 
 pub use cargo_auto_lib as cl;
 
 use crate::cargo_auto_github_api_mod as cgl;
+use crate::ci_mode_mod as ci;
+use crate::dry_run_mod as dr;
 use crate::encrypt_decrypt_with_ssh_key_mod as ende;
 use crate::generic_functions_mod as gn;
-use crate::tasks_mod as ts;
+use crate::github_api_mod as gh;
 
 pub use cl::{BLUE, GREEN, RED, RESET, YELLOW};
 
 // traits must be in scope (Rust strangeness)
 use cl::CargoTomlPublicApiMethods;
+#[allow(unused_imports)]
 use cl::ShellCommandLimitedDoubleQuotesSanitizerTrait;
 
 // region: library with basic automation tasks
@@ -29,19 +44,27 @@ fn main() {
     std::panic::set_hook(Box::new(gn::panic_set_hook));
     gn::tracing_init();
     cl::exit_if_not_run_in_rust_project_root_directory();
-    ende::github_api_token_with_oauth2_mod::github_api_config_initialize();
-    ende::crates_io_api_token_mod::crates_io_config_initialize();
     // get CLI arguments
     let mut args = std::env::args();
     // the zero argument is the name of the program
     let _arg_0 = args.next();
-    match_arguments_and_call_tasks(args);
+    // the global --dry-run flag can be anywhere
+    let args = dr::extract_dry_run_argument(args);
+    // the global --ci flag can be anywhere
+    let args = ci::extract_ci_argument(args.into_iter());
+    // doctor reports the problems of the config files instead of panicking
+    if args.first().map(|arg| arg.as_str()) != Some("doctor") {
+        ende::github_api_token_with_oauth2_mod::github_api_config_initialize();
+        ende::crates_io_api_token_mod::crates_io_config_initialize();
+        deploy_mod::deploy_config_initialize();
+    }
+    match_arguments_and_call_tasks(args.into_iter());
 }
 
 // region: match, help and completion
 
 /// match arguments and call tasks functions
-fn match_arguments_and_call_tasks(mut args: std::env::Args) {
+fn match_arguments_and_call_tasks(mut args: std::vec::IntoIter<String>) {
     // the first argument is the user defined task: (no argument for help), build, release,...
     let arg_1 = args.next();
     match arg_1 {
@@ -53,6 +76,9 @@ fn match_arguments_and_call_tasks(mut args: std::env::Args) {
                 println!("  {YELLOW}Running automation task: {task}{RESET}");
                 if &task == "build" {
                     task_build();
+                } else if &task == "serve" {
+                    let arg_2 = args.next();
+                    task_serve(arg_2);
                 } else if &task == "release" {
                     task_release();
                 } else if &task == "doc" {
@@ -63,9 +89,28 @@ fn match_arguments_and_call_tasks(mut args: std::env::Args) {
                     let arg_2 = args.next();
                     task_commit_and_push(arg_2);
                 } else if &task == "publish_to_web" {
-                    task_publish_to_web();
+                    let arg_2 = args.next();
+                    task_publish_to_web(arg_2);
+                } else if &task == "rollback" {
+                    let arg_2 = args.next();
+                    let arg_3 = args.next();
+                    task_rollback(arg_2, arg_3);
                 } else if &task == "github_new_release" {
                     task_github_new_release();
+                } else if &task == "ship" {
+                    let arg_2 = args.next();
+                    task_ship(arg_2);
+                } else if &task == "rotate_secret_key" {
+                    let arg_2 = args.next();
+                    let arg_3 = args.next();
+                    task_rotate_secret_key(arg_2, arg_3);
+                } else if &task == "secrets" {
+                    task_secrets();
+                } else if &task == "doctor" {
+                    task_doctor();
+                } else if &task == "update_automation_tasks_rs" {
+                    let arg_2 = args.next();
+                    task_update_automation_tasks_rs(arg_2);
                 } else {
                     eprintln!("{RED}Error: Task {task} is unknown.{RESET}");
                     print_help();
@@ -84,7 +129,10 @@ fn print_help() {
 
   {YELLOW}User defined tasks in automation_tasks_rs:{RESET}
 {GREEN}cargo auto build{RESET} - {YELLOW}builds the crate in debug mode, fmt, increment version{RESET}
+{GREEN}cargo auto serve [port]{RESET} - {YELLOW}serves web_server_folder on port 4000, rebuilds on changes in src/ and reloads the page{RESET}
 {GREEN}cargo auto release{RESET} - {YELLOW}builds the crate in release mode, fmt, increment version{RESET}
+  {YELLOW}The pkg files get content-hashed filenames and the .wasm, .js and .css files get .br and .gz siblings.{RESET}
+  {YELLOW}The wasm size is recorded in wasm_size_history.json. It fails if the wasm grows over the budget in wasm_size_config.json.{RESET}
 {GREEN}cargo auto doc{RESET} - {YELLOW}builds the docs, copy to docs directory{RESET}
 {GREEN}cargo auto test{RESET} - {YELLOW}runs all the tests{RESET}
 {GREEN}cargo auto commit_and_push "message"{RESET} - {YELLOW}commits with message and push with mandatory message{RESET}
@@ -95,7 +143,13 @@ fn print_help() {
   {YELLOW}The secret token will be stored in a file encrypted with your SSH private key.{RESET}
   {YELLOW}You can type the passphrase of the private key for every usee. This is pretty secure.{RESET}
   {YELLOW}Somewhat less secure (but more comfortable) way is to store the private key in ssh-agent.{RESET}
-{GREEN}cargo auto publish_to_web{RESET} - {YELLOW}publish to web, git tag{RESET}
+{GREEN}cargo auto publish_to_web [target]{RESET} - {YELLOW}publish to web, git tag{RESET}
+  {YELLOW}The deploy targets are defined in automation_tasks_rs/deploy_config.json.{RESET}
+  {YELLOW}The target of type local_directory copies the files into a local folder, without server and git tag.{RESET}
+  {YELLOW}Every version is deployed into its own folder and activated by an atomic switch of the symlink.{RESET}
+  {YELLOW}Before the first versioned deploy, move the existing folder web_folder/{{package_name}} away.{RESET}
+{GREEN}cargo auto rollback [version|previous] [target]{RESET} - {YELLOW}activates an older deployed version{RESET}
+  {YELLOW}Without version it activates the version before the active one.{RESET}
   {YELLOW}It is preferred to use SSH to publish to web and remotely manage the web server.{RESET}
   {YELLOW}<https://github.com/CRUSTDE-ContainerizedRustDevEnv/crustde_cnt_img_pod/blob/main/ssh_easy.md>{YELLOW}
 {GREEN}cargo auto github_new_release{RESET} - {YELLOW}creates new release on GitHub{RESET}
@@ -103,8 +157,32 @@ fn print_help() {
   {YELLOW}The secret token will be stored in a file encrypted with your SSH private key.{RESET}
   {YELLOW}You can type the passphrase of the private key for every usee. This is pretty secure.{RESET}
   {YELLOW}Somewhat less secure (but more comfortable) way is to store the private key in ssh-agent.{RESET}
-{GREEN}cargo auto update_automation_tasks_rs{RESET} - {YELLOW}updates the files in automation_tasks_rs{RESET}
+{GREEN}cargo auto ship "message"{RESET} - {YELLOW}runs version_increment, build, release, doc, test, commit_and_push, publish_to_web and github_new_release{RESET}
+  {YELLOW}The completed steps are recorded in .ship_state.json. After a failure, run it again to resume from the failed step.{RESET}
+  {YELLOW}The completed build and release are not repeated, so the version is not incremented again.{RESET}
+{GREEN}cargo auto rotate_secret_key old_key_file new_key_file{RESET} - {YELLOW}re-encrypts the stored secret tokens with a new SSH key{RESET}
+  {YELLOW}The encrypted files in ~/.ssh are verified with the new key before they replace the old ones.{RESET}
+  {YELLOW}The config files github_api_config.json and crates_io_config.json are updated to the new key.{RESET}
+{GREEN}cargo auto secrets{RESET} - {YELLOW}lists the encrypted secret tokens in ~/.ssh with purpose, key file and expiry{RESET}
+  {YELLOW}Nothing is decrypted for the list. It offers to delete expired files and to refresh the GitHub tokens.{RESET}
+{GREEN}cargo auto doctor{RESET} - {YELLOW}checks the required tools and versions, the wasm32 target, the config files and SSH keys{RESET}
+  {YELLOW}Every problem is printed with the command or the edit that fixes it.{RESET}
+{GREEN}cargo auto update_automation_tasks_rs "source"{RESET} - {YELLOW}updates the files in automation_tasks_rs{RESET}
+  {YELLOW}The source is a local folder or a git url of a project with automation_tasks_rs.{RESET}
   {YELLOW}Some files are fixed and the update is straight forward, other files need manual diff.{RESET}
+  {YELLOW}Customized files and main.rs are shown as diff and overwritten only after confirmation.{RESET}
+
+  {YELLOW}Add the global flag --dry-run to any task to only print the commands and GitHub API requests, without executing them.{RESET}
+{GREEN}cargo auto commit_and_push "message" --dry-run{RESET}
+
+  {YELLOW}Add the global flag --ci to run the tasks unattended. The tasks never prompt and fail fast instead.{RESET}
+  {YELLOW}The GitHub secret token is read from the environment variable GITHUB_TOKEN{RESET}
+  {YELLOW}or from the file descriptor in GITHUB_TOKEN_FD.{RESET}
+{GREEN}GITHUB_TOKEN_FD=3 cargo auto github_new_release --ci 3< github_token.txt{RESET}
+  {YELLOW}The crates.io secret token is read from CRATES_IO_TOKEN or CRATES_IO_TOKEN_FD.{RESET}
+
+  {YELLOW}For GitHub Enterprise Server set the base urls in the environment variables GITHUB_API_BASE_URL and GITHUB_UPLOADS_BASE_URL.{RESET}
+{GREEN}GITHUB_API_BASE_URL=https://github.example.com/api/v3 cargo auto commit_and_push "message"{RESET}
 
   {YELLOW}© 2025 bestia.dev  MIT License github.com/automation-tasks-rs/cargo-auto{RESET}
 "#
@@ -133,23 +211,27 @@ fn completion() {
     if last_word == "cargo-auto" || last_word == "auto" {
         let sub_commands = vec![
             "build",
+            "serve",
             "release",
             "doc",
             "test",
             "commit_and_push",
             "publish_to_web",
+            "rollback",
             "github_new_release",
+            "ship",
+            "rotate_secret_key",
+            "secrets",
+            "doctor",
             "update_automation_tasks_rs",
         ];
         cl::completion_return_one_or_more_sub_commands(sub_commands, word_being_completed);
     }
-    /*
     // the second level if needed
-    else if last_word == "new" {
-        let sub_commands = vec!["x"];
-       cl::completion_return_one_or_more_sub_commands(sub_commands, word_being_completed);
+    else if last_word == "publish_to_web" {
+        let deploy_config = deploy_mod::DEPLOY_CONFIG.get().unwrap();
+        cl::completion_return_one_or_more_sub_commands(deploy_config.target_names(), word_being_completed);
     }
-    */
 }
 
 // endregion: match, help and completion
@@ -158,12 +240,13 @@ fn completion() {
 
 /// wasm-pack build
 fn task_build() {
-    let cargo_toml = crate::build_wasm_mod::task_build();
+    dr::auto_version_increment_semver_or_date();
+    let cargo_toml = build_without_version_increment();
     println!(
         r#"
-  {YELLOW}After `cargo auto build`, open port 4000 in VSCode and run the basic web server{RESET}
-  {YELLOW}in a separate VSCode bash terminal, so it can serve constantly in the background.{RESET}
-{GREEN}basic-http-server -a 0.0.0.0:4000 ./web_server_folder{RESET}
+  {YELLOW}After `cargo auto build`, open port 4000 in VSCode and run the development web server{RESET}
+  {YELLOW}in a separate VSCode bash terminal. It rebuilds on changes in src/ and reloads the page.{RESET}
+{GREEN}cargo auto serve{RESET}
   {YELLOW}and open the browser on{RESET}
 {GREEN}http://localhost:4000/{package_name}{RESET}
 {GREEN}http://localhost:4000/{package_name}#print/world{RESET}
@@ -178,15 +261,42 @@ fn task_build() {
     print_examples_cmd();
 }
 
+/// The build steps after the version increment. `cargo auto ship` records the version increment as its own step.
+fn build_without_version_increment() -> cl::CargoToml {
+    let cargo_toml = cl::CargoToml::read();
+    dr::run_shell_command_static("cargo fmt").unwrap_or_else(|e| panic!("{e}"));
+    dr::run_shell_command_static("cargo clippy --no-deps").unwrap_or_else(|e| panic!("{e}"));
+    dr::run_shell_command_static("wasm-pack build --target web --profiling").unwrap_or_else(|e| panic!("{e}"));
+
+    dr::ShellCommand::new(r#"rsync -a --delete-after pkg/ "web_server_folder/{package_name}/pkg/" "#)
+        .unwrap_or_else(|e| panic!("{e}"))
+        .arg("{package_name}", &cargo_toml.package_name())
+        .unwrap_or_else(|e| panic!("{e}"))
+        .run()
+        .unwrap_or_else(|e| panic!("{e}"));
+    // return
+    cargo_toml
+}
+
+/// development web server with live reload
+fn task_serve(arg_2: Option<String>) {
+    let port = match arg_2 {
+        None => 4000,
+        Some(port) => port.parse().unwrap_or_else(|_| panic!("{RED}The port `{port}` is not a number.{RESET}")),
+    };
+    let cargo_toml = cl::CargoToml::read();
+    serve_mod::serve(std::path::Path::new("web_server_folder"), &cargo_toml.package_name(), port);
+}
+
 /// wasm-pack build --release
 fn task_release() {
-    let cargo_toml = crate::build_wasm_mod::task_release();
-
+    dr::auto_version_increment_semver_or_date();
+    let cargo_toml = release_without_version_increment();
     println!(
         r#"
-  {YELLOW}After `cargo auto build`, open port 4000 in VSCode and run the basic web server{RESET}
-  {YELLOW}in a separate VSCode bash terminal, so it can serve constantly in the background.{RESET}
-{GREEN}basic-http-server -a 0.0.0.0:4000 ./web_server_folder{RESET}
+  {YELLOW}After `cargo auto build`, open port 4000 in VSCode and run the development web server{RESET}
+  {YELLOW}in a separate VSCode bash terminal. It rebuilds on changes in src/ and reloads the page.{RESET}
+{GREEN}cargo auto serve{RESET}
   {YELLOW}and open the browser on{RESET}
 {GREEN}http://localhost:4000/{package_name}{RESET}    
 {GREEN}http://localhost:4000/{package_name}#print/world{RESET}
@@ -201,9 +311,72 @@ fn task_release() {
     print_examples_cmd();
 }
 
+/// The release steps after the version increment. `cargo auto ship` records the version increment as its own step.
+fn release_without_version_increment() -> cl::CargoToml {
+    dr::auto_cargo_toml_to_md();
+    dr::auto_lines_of_code("");
+
+    dr::run_shell_command_static("cargo fmt").unwrap_or_else(|e| panic!("{e}"));
+    dr::run_shell_command_static("cargo clippy --no-deps").unwrap_or_else(|e| panic!("{e}"));
+    dr::run_shell_command_static("wasm-pack build --target web --release").unwrap_or_else(|e| panic!("{e}"));
+
+    // read after the build, so the report has the version of the built wasm
+    let cargo_toml = cl::CargoToml::read();
+    // the size budget is checked before pkg/ is copied, so a failure leaves the web folders unchanged
+    let wasm_path = format!("pkg/{}_bg.wasm", cargo_toml.package_name());
+    wasm_size_mod::wasm_size_report(camino::Utf8Path::new(&wasm_path), &cargo_toml.package_version())
+        .unwrap_or_else(|e| panic!("{RED}{e}{RESET}"));
+
+    dr::ShellCommand::new(r#"rsync -a --delete-after pkg/ "web_server_folder/{package_name}/pkg/" "#)
+        .unwrap_or_else(|e| panic!("{e}"))
+        .arg("{package_name}", &cargo_toml.package_name())
+        .unwrap_or_else(|e| panic!("{e}"))
+        .run()
+        .unwrap_or_else(|e| panic!("{e}"));
+
+    // the tracked files in web_server_folder are not rewritten, only the copy in the release folder
+    let web_folder = format!("web_server_folder/{}", cargo_toml.package_name());
+    let release_web_folder = format!("{}/{}", release_assets_mod::RELEASE_WEB_SERVER_FOLDER, cargo_toml.package_name());
+    release_assets_mod::fingerprint_and_precompress(
+        camino::Utf8Path::new(&web_folder),
+        camino::Utf8Path::new(&release_web_folder),
+        &cargo_toml.package_name(),
+    )
+    .unwrap_or_else(|e| panic!("{RED}{e}{RESET}"));
+    // return
+    cargo_toml
+}
+
 /// cargo doc, then copies to /docs/ folder, because this is a GitHub standard folder
 fn task_doc() {
-    ts::task_doc();
+    let cargo_toml = cl::CargoToml::read();
+    dr::auto_cargo_toml_to_md();
+    dr::auto_lines_of_code("");
+    // In cargo_auto_lib we have sample data that we don't want to change, avoid this lines.
+    if cargo_toml.package_name() != "cargo_auto_lib" {
+        dr::auto_plantuml(&cargo_toml.package_repository().unwrap());
+        dr::auto_playground_run_code();
+    }
+    dr::auto_md_to_doc_comments();
+
+    dr::run_shell_command_static("cargo doc --no-deps --document-private-items").unwrap_or_else(|e| panic!("{e}"));
+    // copy target/doc into docs/ because it is GitHub standard
+    dr::run_shell_command_static("rsync -a --info=progress2 --delete-after target/doc/ docs/").unwrap_or_else(|e| panic!("{e}"));
+
+    // Create simple index.html file in docs directory
+    dr::ShellCommand::new(
+        r#"printf "<meta http-equiv=\"refresh\" content=\"0; url={url_sanitized_for_double_quote}/index.html\" />\n" > docs/index.html"#,
+    )
+    .unwrap_or_else(|e| panic!("{e}"))
+    .arg("{url_sanitized_for_double_quote}", &cargo_toml.package_name().replace("-", "_"))
+    .unwrap_or_else(|e| panic!("{e}"))
+    .run()
+    .unwrap_or_else(|e| panic!("{e}"));
+
+    // pretty html
+    #[cfg(target_family = "unix")]
+    dr::auto_doc_tidy_html().unwrap_or_else(|e| panic!("{e}"));
+
     // message to help user with next move
     println!(
         r#"
@@ -220,7 +393,7 @@ fn task_doc() {
 /// cargo test
 fn task_test() {
     println!(r#"  {YELLOW}Wasm is a cdylib and therefore doc-tests are not run !{RESET}"#);
-    cl::run_shell_command_static("cargo test").unwrap_or_else(|e| panic!("{e}"));
+    dr::run_shell_command_static("cargo test").unwrap_or_else(|e| panic!("{e}"));
     println!(
         r#"
   {YELLOW}After `cargo auto test`. If ok then {RESET}
@@ -231,8 +404,44 @@ fn task_test() {
 }
 
 /// commit and push
+/// With the GitHub API calls of github_api_mod.
 fn task_commit_and_push(arg_2: Option<String>) {
-    ts::task_commit_and_push(arg_2);
+    let Some(message) = arg_2 else {
+        eprintln!("{RED}Error: Message for commit is mandatory.{RESET}");
+        // early exit
+        return;
+    };
+
+    // If needed, ask to create new local git repository
+    if !cl::git_is_local_repository() {
+        dr::new_local_repository(&message).unwrap();
+    }
+
+    // If needed, ask to create a GitHub remote repository
+    if !cgl::git_has_remote() || !cgl::git_has_upstream() {
+        gh::new_remote_github_repository().unwrap();
+        gh::description_and_topics_to_github();
+    } else {
+        // if description or topics/keywords/tags have changed
+        gh::description_and_topics_to_github();
+
+        // separate commit for docs if they changed, to not make a lot of noise in the real commit
+        if std::path::Path::new("docs").exists() {
+            dr::run_shell_command_static(r#"git add docs && git diff --staged --quiet || git commit -m "update docs" "#)
+                .unwrap_or_else(|e| panic!("{e}"));
+        }
+
+        dr::add_message_to_unreleased(&message);
+        // the real commit of code
+        dr::ShellCommand::new(r#"git add -A && git diff --staged --quiet || git commit -m "{message_sanitized_for_double_quote}" "#)
+            .unwrap_or_else(|e| panic!("{e}"))
+            .arg("{message_sanitized_for_double_quote}", &message)
+            .unwrap_or_else(|e| panic!("{e}"))
+            .run()
+            .unwrap_or_else(|e| panic!("{e}"));
+
+        dr::run_shell_command_static("git push").unwrap_or_else(|e| panic!("{e}"));
+    }
     println!(
         r#"
   {YELLOW}After `cargo auto commit_and_push "message"`{RESET}
@@ -242,63 +451,306 @@ fn task_commit_and_push(arg_2: Option<String>) {
 }
 
 /// publish to web
-fn task_publish_to_web() {
+fn task_publish_to_web(arg_2: Option<String>) {
     let cargo_toml = cl::CargoToml::read();
-    let version = cargo_toml.package_version();
-    // take care of tags
-    let _tag_name_version = cl::git_tag_sync_check_create_push(&version);
-
-    // rsync to copy to server over ssh into a temporary installation folder
-    cl::ShellCommandLimitedDoubleQuotesSanitizer::new(
-r#"rsync -e ssh -a --info=progress2 --delete-after "web_server_folder/{package_name}/" "{server__username}@{web__server__domain}:/var/www/transfer_folder/{package_name}" "#).unwrap_or_else(|e| panic!("{e}"))
-    .arg("{package_name}", &cargo_toml.package_name()).unwrap_or_else(|e| panic!("{e}"))
-    .arg("{server__username}", "luciano_bestia").unwrap_or_else(|e| panic!("{e}"))
-    .arg("{web__server__domain}", "bestia.dev").unwrap_or_else(|e| panic!("{e}"))    
-    .run().unwrap_or_else(|e| panic!("{e}"));
-
-    // rsync to copy to server over ssh the installation script
-    cl::ShellCommandLimitedDoubleQuotesSanitizer::new(
-r#"rsync -e ssh -a --info=progress2 --delete-after "publish_script/{package_name}_publish.sh" "{server__username}@{web__server__domain}:/var/www/scripts/{package_name}/" "#).unwrap_or_else(|e| panic!("{e}"))
-    .arg("{package_name}", &cargo_toml.package_name()).unwrap_or_else(|e| panic!("{e}"))
-    .arg("{server__username}", "luciano_bestia").unwrap_or_else(|e| panic!("{e}"))
-    .arg("{web__server__domain}", "bestia.dev").unwrap_or_else(|e| panic!("{e}"))    
-    .run().unwrap_or_else(|e| panic!("{e}"));
-
-    //make the bash script executable
-    cl::ShellCommandLimitedDoubleQuotesSanitizer::new(
-r#"ssh "{server__username}@{web__server__domain}" chmod +x  "/var/www/scripts/{package_name}/{package_name}_publish.sh" "#).unwrap_or_else(|e| panic!("{e}"))
-    .arg("{package_name}", &cargo_toml.package_name()).unwrap_or_else(|e| panic!("{e}"))
-    .arg("{server__username}", "luciano_bestia").unwrap_or_else(|e| panic!("{e}"))
-    .arg("{web__server__domain}", "bestia.dev").unwrap_or_else(|e| panic!("{e}"))    
-    .run().unwrap_or_else(|e| panic!("{e}"));
-
-    // run installation script over ssh on the server to copy from the installation folder to production folder
-    cl::ShellCommandLimitedDoubleQuotesSanitizer::new(
-r#"ssh "{server__username}@{web__server__domain}" "/var/www/scripts/{package_name}/{package_name}_publish.sh" "#).unwrap_or_else(|e| panic!("{e}"))
-    .arg("{package_name}", &cargo_toml.package_name()).unwrap_or_else(|e| panic!("{e}"))
-    .arg("{server__username}", "luciano_bestia").unwrap_or_else(|e| panic!("{e}"))
-    .arg("{web__server__domain}", "bestia.dev").unwrap_or_else(|e| panic!("{e}"))    
-    .run().unwrap_or_else(|e| panic!("{e}"));
+    let deploy_target = deploy_mod::DEPLOY_CONFIG
+        .get()
+        .unwrap()
+        .target(arg_2.as_deref())
+        .unwrap_or_else(|e| panic!("{RED}{e}{RESET}"));
+    println!("  {YELLOW}Deploy target: {}{RESET}", deploy_target.name);
+    if let deploy_mod::DeployTargetKind::Ssh { .. } = deploy_target.kind {
+        let version = cargo_toml.package_version();
+        // take care of tags
+        let _tag_name_version = dr::git_tag_sync_check_create_push(&version);
+    }
+
+    let package_name = cargo_toml.package_name();
+    // only the release build has the fingerprinted and precompressed files
+    let release_web_folder = format!("{}/{package_name}", release_assets_mod::RELEASE_WEB_SERVER_FOLDER);
+    if !dr::is_dry_run() && !std::path::Path::new(&release_web_folder).exists() {
+        panic!("{RED}The release folder {release_web_folder} does not exist. Run `cargo auto release` first.{RESET}");
+    }
+    let published_location = deploy_target
+        .deploy(
+            &release_web_folder,
+            &package_name,
+            &cargo_toml.package_version(),
+        )
+        .unwrap_or_else(|e| panic!("{RED}{e}{RESET}"));
 
     println!(
         r#"
   {YELLOW}After `cargo auto publish_to_web`check {RESET}
-{GREEN}https://bestia.dev/{package_name}{RESET}
+{GREEN}{published_location}{RESET}
   {YELLOW}Now, write the content of the release in the RELEASES.md in the `## Unreleased` section, then{RESET}
   {YELLOW}Next, create the GitHub Release.{RESET}
 {GREEN}cargo auto github_new_release{RESET}
+"#
+    );
+}
+
+/// activate an older deployed version
+fn task_rollback(arg_2: Option<String>, arg_3: Option<String>) {
+    let cargo_toml = cl::CargoToml::read();
+    let requested_version = arg_2.filter(|version| version != "previous");
+    let deploy_target = deploy_mod::DEPLOY_CONFIG
+        .get()
+        .unwrap()
+        .target(arg_3.as_deref())
+        .unwrap_or_else(|e| panic!("{RED}{e}{RESET}"));
+    let package_name = cargo_toml.package_name();
+    let version = deploy_target
+        .rollback(&package_name, requested_version.as_deref())
+        .unwrap_or_else(|e| panic!("{RED}{e}{RESET}"));
+    println!(
+        r#"
+  {YELLOW}After `cargo auto rollback` the version {version} is active. Check {RESET}
+{GREEN}{published_location}{RESET}
 "#,
-        package_name = cargo_toml.package_name()
+        published_location = deploy_target.published_location(&package_name)
+    );
+}
+
+/// run the whole workflow from version_increment to github_new_release, resumable after a failure
+fn task_ship(arg_2: Option<String>) {
+    let Some(message) = arg_2 else {
+        eprintln!("{RED}Error: Message for commit is mandatory.{RESET}");
+        // early exit
+        return;
+    };
+    let build = || {
+        build_without_version_increment();
+    };
+    let release = || {
+        release_without_version_increment();
+    };
+    let commit_and_push = || task_commit_and_push(Some(message.clone()));
+    let publish_to_web = || task_publish_to_web(None);
+    let steps: [ship_mod::ShipStep; 8] = [
+        ("version_increment", &dr::auto_version_increment_semver_or_date),
+        ("build", &build),
+        ("release", &release),
+        ("doc", &task_doc),
+        ("test", &task_test),
+        ("commit_and_push", &commit_and_push),
+        ("publish_to_web", &publish_to_web),
+        ("github_new_release", &task_github_new_release),
+    ];
+    ship_mod::ship(
+        &message,
+        &steps,
+        camino::Utf8Path::new(ship_mod::SHIP_STATE_FILE),
+        &|| cl::CargoToml::read().package_version(),
+    )
+    .unwrap_or_else(|e| panic!("{RED}{e}{RESET}"));
+}
+
+/// re-encrypt the stored secret tokens with a new ssh key
+fn task_rotate_secret_key(arg_2: Option<String>, arg_3: Option<String>) {
+    let (Some(old_private_key_file_name), Some(new_private_key_file_name)) = (arg_2, arg_3) else {
+        eprintln!("{RED}Error: The task rotate_secret_key needs the old and the new private key file names from ~/.ssh.{RESET}");
+        print_help();
+        return;
+    };
+    rotate_secret_key_mod::rotate_secret_key(&old_private_key_file_name, &new_private_key_file_name)
+        .unwrap_or_else(|e| panic!("{RED}{e}{RESET}"));
+    println!(
+        r#"
+  {YELLOW}After `cargo auto rotate_secret_key` the secret tokens are encrypted with the new key. {RESET}
+  {YELLOW}Commit the changed config files and retire the old key when you are ready. {RESET}
+"#
     );
 }
 
+/// list, inspect and delete the stored secret tokens
+fn task_secrets() {
+    secrets_mod::secrets().unwrap_or_else(|e| panic!("{RED}{e}{RESET}"));
+}
+
+/// check the tools and the configuration before running the other tasks
+fn task_doctor() {
+    doctor_mod::doctor().unwrap_or_else(|e| panic!("{RED}{e}{RESET}"));
+}
+
 /// create a new release on github
+/// With the GitHub API calls of github_api_mod.
+/// This project has no executables, the release assets are the web app bundle.
 fn task_github_new_release() {
-    ts::task_github_new_release();
+    let cargo_toml = cl::CargoToml::read();
+    let version = cargo_toml.package_version();
+    // take care of tags
+    let tag_name_version = dr::git_tag_sync_check_create_push(&version);
+
+    let github_owner = cargo_toml.github_owner().unwrap();
+    let repo_name = cargo_toml.package_name();
+    let now_date = cl::now_utc_date_iso();
+    let release_name = format!("Version {} ({})", &version, now_date);
+    let branch = "main";
+
+    // A failed github_new_release can be resumed, then the release for the tag already exists.
+    let existing_release =
+        gh::existing_release_for_tag(&github_owner, &repo_name, &tag_name_version).unwrap_or_else(|e| panic!("{RED}{e}{RESET}"));
+    let (release_id, uploaded_asset_names) = match existing_release {
+        Some(existing_release) => {
+            println!("  {YELLOW}Resume the existing GitHub release for the tag {tag_name_version}.{RESET}");
+            (existing_release.release_id, existing_release.asset_names)
+        }
+        None => {
+            // First, the user must write the content into file RELEASES.md in the section ## Unreleased.
+            // Then the automation task will copy the content to GitHub release.
+            // A failed run already moved the content into the Version section.
+            let version_body_text = match body_text_of_version_in_releases_md(&version) {
+                Some(version_body_text) => version_body_text,
+                None => {
+                    let version_body_text = cl::body_text_from_releases_md().unwrap();
+                    // Create a new Version title and modify RELEASES.md.
+                    dr::create_new_version_in_releases_md(&release_name).unwrap();
+                    version_body_text
+                }
+            };
+
+            // Commit and push of modified Version in RELEASES.md
+            dr::ShellCommand::new(r#"git add -A && git diff --staged --quiet || git commit -m "{message_sanitized_for_double_quote}" "#)
+                .unwrap_or_else(|e| panic!("{e}"))
+                .arg("{message_sanitized_for_double_quote}", &release_name)
+                .unwrap_or_else(|e| panic!("{e}"))
+                .run()
+                .unwrap_or_else(|e| panic!("{e}"));
+
+            dr::run_shell_command_static("git push").unwrap_or_else(|e| panic!("{e}"));
+
+            // GitHub api call to create the Release
+            let request = cgl::github_api_create_new_release(
+                &github_owner,
+                &repo_name,
+                &tag_name_version,
+                &release_name,
+                branch,
+                &version_body_text,
+            );
+            let json_value = gh::send_to_github_api(request).unwrap();
+            // early exit on error
+            let release_id = gh::release_id_from_json(&json_value).unwrap_or_else(|e| panic!("{RED}{e}{RESET}"));
+
+            println!("  {YELLOW}New GitHub release created: {release_name}.{RESET}");
+            (release_id, vec![])
+        }
+    };
+
+    // region: upload asset for wasm web apps
+
+    println!("  {YELLOW}Now uploading release asset. This can take some time if the files are big. Wait...{RESET}");
+
+    // The release web app folder with pkg/ is packaged, so anyone can self-host a given version.
+    let release_folder = release_assets_mod::RELEASE_WEB_SERVER_FOLDER;
+    let web_app_path = format!("{release_folder}/{repo_name}");
+    let tar_gz_name = format!("{repo_name}-{tag_name_version}-web.tar.gz");
+    let zip_name = format!("{repo_name}-{tag_name_version}-web.zip");
+    let checksum_name = format!("{repo_name}-{tag_name_version}-web-SHA256SUMS.txt");
+    let uploaded_count = [&tar_gz_name, &zip_name, &checksum_name]
+        .iter()
+        .filter(|asset_name| uploaded_asset_names.contains(asset_name))
+        .count();
+    if uploaded_count == 3 {
+        println!("  {YELLOW}The web app assets are already uploaded.{RESET}");
+    } else if uploaded_count > 0 {
+        // the new archives can differ from the uploaded ones and the checksums must match
+        panic!("{RED}The release {tag_name_version} has only some of the web app assets. Delete them on GitHub Releases and run `cargo auto github_new_release` again.{RESET}");
+    } else if dr::is_dry_run() || std::fs::exists(format!("{web_app_path}/pkg")).unwrap() {
+        dr::ShellCommand::new(
+            r#"tar -zcvf "{tar_gz_name_sanitized_for_double_quote}" -C "{release_folder_sanitized_for_double_quote}" "{repo_name_sanitized_for_double_quote}" "#,
+        )
+        .unwrap_or_else(|e| panic!("{e}"))
+        .arg("{tar_gz_name_sanitized_for_double_quote}", &tar_gz_name)
+        .unwrap_or_else(|e| panic!("{e}"))
+        .arg("{release_folder_sanitized_for_double_quote}", release_folder)
+        .unwrap_or_else(|e| panic!("{e}"))
+        .arg("{repo_name_sanitized_for_double_quote}", &repo_name)
+        .unwrap_or_else(|e| panic!("{e}"))
+        .run()
+        .unwrap_or_else(|e| panic!("{e}"));
+
+        // the zip paths are relative to the release folder target/web_server_folder, just like in tar.gz
+        dr::ShellCommand::new(
+            r#"cd "{release_folder_sanitized_for_double_quote}" && zip -r "../../{zip_name_sanitized_for_double_quote}" "{repo_name_sanitized_for_double_quote}" "#,
+        )
+        .unwrap_or_else(|e| panic!("{e}"))
+        .arg("{release_folder_sanitized_for_double_quote}", release_folder)
+        .unwrap_or_else(|e| panic!("{e}"))
+        .arg("{zip_name_sanitized_for_double_quote}", &zip_name)
+        .unwrap_or_else(|e| panic!("{e}"))
+        .arg("{repo_name_sanitized_for_double_quote}", &repo_name)
+        .unwrap_or_else(|e| panic!("{e}"))
+        .run()
+        .unwrap_or_else(|e| panic!("{e}"));
+
+        // check after download with: sha256sum -c snake_bevy_wasm_pwa-v1.0.0-web-SHA256SUMS.txt
+        dr::ShellCommand::new(
+            r#"sha256sum "{tar_gz_name_sanitized_for_double_quote}" "{zip_name_sanitized_for_double_quote}" > "{checksum_name_sanitized_for_double_quote}" "#,
+        )
+        .unwrap_or_else(|e| panic!("{e}"))
+        .arg("{tar_gz_name_sanitized_for_double_quote}", &tar_gz_name)
+        .unwrap_or_else(|e| panic!("{e}"))
+        .arg("{zip_name_sanitized_for_double_quote}", &zip_name)
+        .unwrap_or_else(|e| panic!("{e}"))
+        .arg("{checksum_name_sanitized_for_double_quote}", &checksum_name)
+        .unwrap_or_else(|e| panic!("{e}"))
+        .run()
+        .unwrap_or_else(|e| panic!("{e}"));
+
+        for asset_name in [&tar_gz_name, &zip_name, &checksum_name] {
+            // upload asset
+            gh::github_api_upload_asset_to_release(&github_owner, &repo_name, &release_id, asset_name)
+                .unwrap_or_else(|e| panic!("{RED}{e}{RESET}"));
+
+            dr::ShellCommand::new(r#"rm "{asset_name_sanitized_for_double_quote}" "#)
+                .unwrap_or_else(|e| panic!("{e}"))
+                .arg("{asset_name_sanitized_for_double_quote}", asset_name)
+                .unwrap_or_else(|e| panic!("{e}"))
+                .run()
+                .unwrap_or_else(|e| panic!("{e}"));
+        }
+        println!(r#"  {YELLOW}Web app assets uploaded. Open and edit the description on GitHub Releases in the browser.{RESET}"#);
+    } else {
+        // the release exists now, so the next github_new_release only uploads the assets
+        panic!("{RED}The release folder {web_app_path}/pkg does not exist. Run `cargo auto release` first, then `cargo auto github_new_release` again.{RESET}");
+    }
+    // endregion: upload asset for wasm web apps
+
+    println!(r#"{GREEN}https://github.com/{github_owner}/{repo_name}/releases{RESET} "#);
     println!(
         r#"
   {YELLOW}No more automation tasks. {RESET}
 "#
     );
 }
+
+/// The release notes from the Version section of RELEASES.md, if a failed github_new_release already created it.
+fn body_text_of_version_in_releases_md(version: &str) -> Option<String> {
+    let releases_md = std::fs::read_to_string("RELEASES.md").ok()?;
+    let version_title = format!("## Version {version} (");
+    let start = releases_md.find(&version_title)?;
+    let section = &releases_md[start..];
+    // the section ends with the next title
+    let section = section[1..].find("\n## ").map_or(section, |end| &section[..end + 1]);
+    let body_text = section.split_once('\n').map_or("", |(_title, body_text)| body_text);
+    Some(body_text.trim().to_string())
+}
+
+/// update the generic files in automation_tasks_rs
+fn task_update_automation_tasks_rs(arg_2: Option<String>) {
+    let Some(source) = arg_2 else {
+        eprintln!("{RED}Error: The source folder or git url is mandatory.{RESET}");
+        // early exit
+        return;
+    };
+    update_automation_tasks_rs_mod::update_automation_tasks_rs(&source).unwrap_or_else(|e| panic!("{RED}{e}{RESET}"));
+    println!(
+        r#"
+  {YELLOW}After `cargo auto update_automation_tasks_rs` check the changes with git diff and run{RESET}
+{GREEN}cargo auto build{RESET}
+"#
+    );
+}
 // endregion: tasks
@@ -0,0 +1,424 @@
+// release_assets_mod.rs
+
+//! Content-hashed pkg filenames and precompressed assets for the release build.
+//!
+//! The web server can send the files with far-future caching only if every change gets a new filename.
+//! The release copies `web_server_folder/{package_name}` into the release folder `target/web_server_folder/{package_name}`,
+//! so the tracked `index.html` and `service_worker.js` are never rewritten and don't change on every release.
+//! In the copy it renames `{package_name}_bg.wasm` and `{package_name}.js` in `pkg/` with the hash of their content
+//! and rewrites the references in the javascript, in `index.html` and in `service_worker.js`.
+//! The `CACHE_NAME` in `service_worker.js` gets the hash of all the files in the web folder,
+//! so the cache-first service worker installs the new files instead of serving the old ones from its cache,
+//! also when only `index.html`, a css file or an icon changes.
+//! Then every `.wasm`, `.js` and `.css` file gets the Brotli `.br` and gzip `.gz` sibling,
+//! so the web server does not compress them on every request.
+//! `publish_to_web` and `github_new_release` use the release folder.
+
+use std::io::Write;
+
+use sha2::Digest;
+
+use crate::cl;
+use crate::dry_run_mod as dr;
+
+#[allow(unused_imports)]
+use cl::{BLUE, GREEN, RED, RESET, YELLOW};
+
+/// The release copies of the web folders. It is in target/, so it is not tracked by git.
+pub const RELEASE_WEB_SERVER_FOLDER: &str = "target/web_server_folder";
+
+/// The files with these extensions are precompressed.
+const PRECOMPRESSED_EXTENSIONS: [&str; 3] = ["wasm", "js", "css"];
+
+/// The files that reference the pkg files: the file name and the prefix of the path in the quotes.
+const FILES_WITH_PKG_REFERENCES: [(&str, &str); 2] = [("index.html", "./pkg/"), ("service_worker.js", "pkg/")];
+
+/// Copy the web folder into the release folder, then rename the pkg files with the content hash,
+/// rewrite the references and precompress the assets in the copy.
+pub fn fingerprint_and_precompress(
+    web_folder: &camino::Utf8Path,
+    release_web_folder: &camino::Utf8Path,
+    package_name: &str,
+) -> anyhow::Result<()> {
+    if dr::skip(&format!(
+        "copy {web_folder} into {release_web_folder}, fingerprint the pkg files and precompress the assets"
+    )) {
+        return Ok(());
+    }
+    if release_web_folder.exists() {
+        std::fs::remove_dir_all(release_web_folder)?;
+    }
+    copy_folder(web_folder, release_web_folder)?;
+
+    let pkg_folder = release_web_folder.join("pkg");
+    let wasm_file_name = format!("{package_name}_bg.wasm");
+    let js_file_name = format!("{package_name}.js");
+
+    // the javascript loads the wasm file, so its content changes with the wasm hash
+    let wasm_bytes = std::fs::read(pkg_folder.join(&wasm_file_name))?;
+    let hashed_wasm_file_name = hashed_file_name(&wasm_file_name, &wasm_bytes);
+    std::fs::rename(pkg_folder.join(&wasm_file_name), pkg_folder.join(&hashed_wasm_file_name))?;
+
+    let js_text = std::fs::read_to_string(pkg_folder.join(&js_file_name))?;
+    let Some(js_text) = rewrite_pkg_reference(&js_text, "", &wasm_file_name, &hashed_wasm_file_name) else {
+        anyhow::bail!("The file {js_file_name} does not reference {wasm_file_name}.");
+    };
+    let hashed_js_file_name = hashed_file_name(&js_file_name, js_text.as_bytes());
+    std::fs::write(pkg_folder.join(&hashed_js_file_name), &js_text)?;
+    std::fs::remove_file(pkg_folder.join(&js_file_name))?;
+
+    rewrite_pkg_references_in_web_folder(release_web_folder, package_name, &hashed_js_file_name, &hashed_wasm_file_name)?;
+    rewrite_cache_name_in_service_worker(release_web_folder, &folder_content_hash(release_web_folder)?)?;
+    println!("  {YELLOW}Fingerprinted pkg/{hashed_js_file_name} and pkg/{hashed_wasm_file_name} in {release_web_folder}{RESET}");
+
+    precompress_assets(release_web_folder)?;
+    Ok(())
+}
+
+/// The hash of the paths and contents of all files in the folder and subfolders.
+///
+/// The service worker caches the files of the web folder, so every change gets a new CACHE_NAME.
+/// It is called before the precompression, so the `.br` and `.gz` files are not in the hash.
+fn folder_content_hash(folder: &camino::Utf8Path) -> anyhow::Result<String> {
+    let mut file_paths = Vec::new();
+    collect_file_paths(folder, &mut file_paths)?;
+    // the order of read_dir is not defined
+    file_paths.sort();
+    let mut hasher = sha2::Sha256::new();
+    for file_path in file_paths {
+        let relative_path = file_path.strip_prefix(folder)?;
+        hasher.update(relative_path.as_str().as_bytes());
+        hasher.update([0]);
+        let content = std::fs::read(&file_path)?;
+        hasher.update((content.len() as u64).to_le_bytes());
+        hasher.update(&content);
+    }
+    let hash = hasher.finalize();
+    Ok(hash.iter().take(8).map(|byte| format!("{byte:02x}")).collect())
+}
+
+/// Collect the paths of all files in the folder and subfolders.
+fn collect_file_paths(folder: &camino::Utf8Path, file_paths: &mut Vec<camino::Utf8PathBuf>) -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(folder)? {
+        let path = camino::Utf8PathBuf::try_from(entry?.path())?;
+        if path.is_dir() {
+            collect_file_paths(&path, file_paths)?;
+        } else {
+            file_paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Copy all files from the folder and subfolders.
+fn copy_folder(folder: &camino::Utf8Path, new_folder: &camino::Utf8Path) -> anyhow::Result<()> {
+    std::fs::create_dir_all(new_folder)?;
+    for entry in std::fs::read_dir(folder)? {
+        let path = camino::Utf8PathBuf::try_from(entry?.path())?;
+        let new_path = new_folder.join(path.file_name().unwrap_or_default());
+        if path.is_dir() {
+            copy_folder(&path, &new_path)?;
+        } else {
+            std::fs::copy(&path, &new_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Rewrite the references to the javascript and the wasm file in index.html and service_worker.js.
+fn rewrite_pkg_references_in_web_folder(
+    web_folder: &camino::Utf8Path,
+    package_name: &str,
+    new_js_file_name: &str,
+    new_wasm_file_name: &str,
+) -> anyhow::Result<()> {
+    for (file_name, path_prefix) in FILES_WITH_PKG_REFERENCES {
+        let path = web_folder.join(file_name);
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let mut new_text = text.clone();
+        for (plain_file_name, new_file_name) in [
+            (format!("{package_name}.js"), new_js_file_name),
+            (format!("{package_name}_bg.wasm"), new_wasm_file_name),
+        ] {
+            if let Some(rewritten_text) = rewrite_pkg_reference(&new_text, path_prefix, &plain_file_name, new_file_name) {
+                new_text = rewritten_text;
+            }
+        }
+        if file_name == "index.html" && !new_text.contains(&format!(r#"import init from "{path_prefix}{new_js_file_name}""#)) {
+            anyhow::bail!(r#"The file {path} does not contain the line `import init from "{path_prefix}{package_name}.js"`."#);
+        }
+        if new_text != text {
+            std::fs::write(&path, new_text)?;
+        }
+    }
+    Ok(())
+}
+
+/// The service worker is cache-first, so a new CACHE_NAME is needed to install the new files.
+fn rewrite_cache_name_in_service_worker(web_folder: &camino::Utf8Path, cache_name: &str) -> anyhow::Result<()> {
+    let path = web_folder.join("service_worker.js");
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return Ok(());
+    };
+    let Some(new_text) = rewrite_cache_name(&text, cache_name) else {
+        anyhow::bail!("The file {path} does not contain the line `const CACHE_NAME = '...';`.");
+    };
+    std::fs::write(&path, new_text)?;
+    Ok(())
+}
+
+/// Replace the value in `const CACHE_NAME = '...';`. Returns None if there is no such line.
+fn rewrite_cache_name(text: &str, cache_name: &str) -> Option<String> {
+    let prefix = "const CACHE_NAME = '";
+    let start = text.find(prefix)? + prefix.len();
+    let end = start + text[start..].find('\'')?;
+    Some(format!("{}{cache_name}{}", &text[..start], &text[end..]))
+}
+
+/// The first 16 hex digits of the SHA256 of the content.
+fn content_hash(content: &[u8]) -> String {
+    let hash = sha2::Sha256::digest(content);
+    hash.iter().take(8).map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// `{stem}.{hash}.{extension}` with the content hash.
+fn hashed_file_name(file_name: &str, content: &[u8]) -> String {
+    let hash = content_hash(content);
+    match file_name.rsplit_once('.') {
+        Some((stem, extension)) => format!("{stem}.{hash}.{extension}"),
+        None => format!("{file_name}.{hash}"),
+    }
+}
+
+/// Replace every quoted reference `{path_prefix}{file_name}` with the plain or hashed file name.
+///
+/// Returns None if there is no reference.
+fn rewrite_pkg_reference(text: &str, path_prefix: &str, plain_file_name: &str, new_file_name: &str) -> Option<String> {
+    let (stem, extension) = plain_file_name.rsplit_once('.')?;
+    let mut new_text = String::with_capacity(text.len());
+    let mut is_found = false;
+    let mut rest = text;
+    while let Some(position) = rest.find(&format!("{path_prefix}{stem}")) {
+        let quote = rest[..position].chars().next_back();
+        let start = position + path_prefix.len();
+        let end = rest[start..].find(['"', '\'']).map(|end| start + end);
+        match (quote, end) {
+            (Some(quote @ ('"' | '\'')), Some(end))
+                if rest[end..].starts_with(quote) && is_plain_or_hashed(&rest[start..end], stem, extension) =>
+            {
+                new_text.push_str(&rest[..start]);
+                new_text.push_str(new_file_name);
+                rest = &rest[end..];
+                is_found = true;
+            }
+            _ => {
+                let next = position + path_prefix.len() + stem.len();
+                new_text.push_str(&rest[..next]);
+                rest = &rest[next..];
+            }
+        }
+    }
+    new_text.push_str(rest);
+    is_found.then_some(new_text)
+}
+
+/// `stem.extension` or `stem.{hex}.extension`
+fn is_plain_or_hashed(file_name: &str, stem: &str, extension: &str) -> bool {
+    let Some(middle) = file_name.strip_prefix(stem).and_then(|x| x.strip_suffix(extension)) else {
+        return false;
+    };
+    middle == "."
+        || middle
+            .strip_prefix('.')
+            .and_then(|x| x.strip_suffix('.'))
+            .is_some_and(|hash| !hash.is_empty() && hash.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Write the `.br` and `.gz` siblings of all the `.wasm`, `.js` and `.css` files in the folder and subfolders.
+fn precompress_assets(folder: &camino::Utf8Path) -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(folder)? {
+        let path = camino::Utf8PathBuf::try_from(entry?.path())?;
+        if path.is_dir() {
+            precompress_assets(&path)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| PRECOMPRESSED_EXTENSIONS.contains(&extension))
+        {
+            let content = std::fs::read(&path)?;
+            std::fs::write(format!("{path}.br"), compress_brotli(&content)?)?;
+            std::fs::write(format!("{path}.gz"), compress_gzip(&content)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// The best quality, because it is compressed once and sent many times.
+fn compress_brotli(content: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut compressor = brotli::CompressorWriter::new(Vec::new(), 4096, 11, 22);
+    compressor.write_all(content)?;
+    compressor.flush()?;
+    Ok(compressor.into_inner())
+}
+
+/// The best compression level.
+fn compress_gzip(content: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(content)?;
+    Ok(encoder.finish()?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    pub fn test_rewrite_pkg_reference() {
+        let index_html = r#"        import init from "./pkg/snake_bevy_wasm_pwa.js";"#;
+        let hashed_file_name = hashed_file_name("snake_bevy_wasm_pwa.js", b"content");
+        assert_eq!(hashed_file_name, "snake_bevy_wasm_pwa.ed7002b439e9ac84.js");
+        let rewritten = rewrite_pkg_reference(index_html, "./pkg/", "snake_bevy_wasm_pwa.js", &hashed_file_name).unwrap();
+        assert_eq!(
+            rewritten,
+            r#"        import init from "./pkg/snake_bevy_wasm_pwa.ed7002b439e9ac84.js";"#
+        );
+        // a hashed reference is recognized and replaced too
+        let restored = rewrite_pkg_reference(&rewritten, "./pkg/", "snake_bevy_wasm_pwa.js", "snake_bevy_wasm_pwa.js").unwrap();
+        assert_eq!(restored, index_html);
+
+        // the wasm file name starts with the same stem, it must not be touched by the js rewrite
+        let service_worker = "'pkg/snake_bevy_wasm_pwa_bg.wasm',\n'pkg/snake_bevy_wasm_pwa.js'";
+        let rewritten = rewrite_pkg_reference(service_worker, "pkg/", "snake_bevy_wasm_pwa.js", "snake_bevy_wasm_pwa.0a1b.js").unwrap();
+        assert_eq!(rewritten, "'pkg/snake_bevy_wasm_pwa_bg.wasm',\n'pkg/snake_bevy_wasm_pwa.0a1b.js'");
+
+        let js = "module_or_path = new URL('snake_bevy_wasm_pwa_bg.wasm', import.meta.url);";
+        let rewritten = rewrite_pkg_reference(js, "", "snake_bevy_wasm_pwa_bg.wasm", "snake_bevy_wasm_pwa_bg.ff00.wasm").unwrap();
+        assert_eq!(
+            rewritten,
+            "module_or_path = new URL('snake_bevy_wasm_pwa_bg.ff00.wasm', import.meta.url);"
+        );
+
+        assert_eq!(
+            rewrite_pkg_reference("no reference", "pkg/", "snake_bevy_wasm_pwa.js", "x.js"),
+            None
+        );
+    }
+
+    #[test]
+    pub fn test_fingerprint_and_precompress() {
+        let test_folder =
+            camino::Utf8PathBuf::from_path_buf(std::env::temp_dir().join(format!("test_release_assets_{}", std::process::id()))).unwrap();
+        let web_folder = test_folder.join("web_server_folder/snake");
+        let release_web_folder = test_folder.join("target/web_server_folder/snake");
+        std::fs::create_dir_all(web_folder.join("pkg")).unwrap();
+        std::fs::write(web_folder.join("pkg/snake_bg.wasm"), b"wasm").unwrap();
+        std::fs::write(web_folder.join("pkg/snake.js"), "new URL('snake_bg.wasm', import.meta.url);").unwrap();
+        std::fs::write(web_folder.join("index.html"), r#"import init from "./pkg/snake.js";"#).unwrap();
+        let service_worker = "const CACHE_NAME = '2024.501.223';\n'pkg/snake_bg.wasm',\n'pkg/snake.js'";
+        std::fs::write(web_folder.join("service_worker.js"), service_worker).unwrap();
+        // the old release folder is replaced
+        std::fs::create_dir_all(release_web_folder.join("pkg")).unwrap();
+        std::fs::write(release_web_folder.join("pkg/old.js"), "old").unwrap();
+
+        fingerprint_and_precompress(&web_folder, &release_web_folder, "snake").unwrap();
+        let hashed_wasm_file_name = hashed_file_name("snake_bg.wasm", b"wasm");
+        let js_text = format!("new URL('{hashed_wasm_file_name}', import.meta.url);");
+        let hashed_js_file_name = hashed_file_name("snake.js", js_text.as_bytes());
+        assert_eq!(
+            std::fs::read_to_string(release_web_folder.join("pkg").join(&hashed_js_file_name)).unwrap(),
+            js_text
+        );
+        assert!(!release_web_folder.join("pkg/snake.js").exists());
+        assert!(!release_web_folder.join("pkg/old.js").exists());
+        assert!(release_web_folder.join("pkg").join(format!("{hashed_wasm_file_name}.br")).exists());
+        assert!(release_web_folder.join("pkg").join(format!("{hashed_wasm_file_name}.gz")).exists());
+        assert_eq!(
+            std::fs::read_to_string(release_web_folder.join("index.html")).unwrap(),
+            format!(r#"import init from "./pkg/{hashed_js_file_name}";"#)
+        );
+        let new_service_worker = std::fs::read_to_string(release_web_folder.join("service_worker.js")).unwrap();
+        let cache_name = new_service_worker.split('\'').nth(1).unwrap().to_string();
+        assert_eq!(cache_name.len(), 16);
+        assert_ne!(cache_name, "2024.501.223");
+        assert_eq!(
+            new_service_worker,
+            format!("const CACHE_NAME = '{cache_name}';\n'pkg/{hashed_wasm_file_name}',\n'pkg/{hashed_js_file_name}'")
+        );
+
+        // a change in a file other than the pkg files gets a new CACHE_NAME too
+        std::fs::write(
+            web_folder.join("index.html"),
+            r#"<title>Snake</title>import init from "./pkg/snake.js";"#,
+        )
+        .unwrap();
+        fingerprint_and_precompress(&web_folder, &release_web_folder, "snake").unwrap();
+        let new_service_worker = std::fs::read_to_string(release_web_folder.join("service_worker.js")).unwrap();
+        assert!(release_web_folder.join("pkg").join(&hashed_js_file_name).exists());
+        assert!(!new_service_worker.contains(&cache_name));
+        std::fs::write(web_folder.join("index.html"), r#"import init from "./pkg/snake.js";"#).unwrap();
+        fingerprint_and_precompress(&web_folder, &release_web_folder, "snake").unwrap();
+
+        // the tracked files are not changed
+        assert_eq!(
+            std::fs::read_to_string(web_folder.join("index.html")).unwrap(),
+            r#"import init from "./pkg/snake.js";"#
+        );
+        assert_eq!(
+            std::fs::read_to_string(web_folder.join("service_worker.js")).unwrap(),
+            service_worker
+        );
+        assert!(web_folder.join("pkg/snake.js").exists());
+        assert!(!web_folder.join("pkg/snake.js.br").exists());
+        std::fs::remove_dir_all(&test_folder).unwrap();
+    }
+
+    #[test]
+    pub fn test_folder_content_hash() {
+        let test_folder =
+            camino::Utf8PathBuf::from_path_buf(std::env::temp_dir().join(format!("test_folder_content_hash_{}", std::process::id())))
+                .unwrap();
+        std::fs::create_dir_all(test_folder.join("icons")).unwrap();
+        std::fs::write(test_folder.join("index.html"), "index").unwrap();
+        std::fs::write(test_folder.join("icons/icon-032.png"), "icon").unwrap();
+        let hash = folder_content_hash(&test_folder).unwrap();
+        assert_eq!(hash.len(), 16);
+        assert_eq!(folder_content_hash(&test_folder).unwrap(), hash);
+
+        std::fs::write(test_folder.join("icons/icon-032.png"), "new icon").unwrap();
+        let new_hash = folder_content_hash(&test_folder).unwrap();
+        assert_ne!(new_hash, hash);
+
+        // the same content in a renamed file is a change too
+        std::fs::rename(test_folder.join("icons/icon-032.png"), test_folder.join("icons/icon-072.png")).unwrap();
+        assert_ne!(folder_content_hash(&test_folder).unwrap(), new_hash);
+        std::fs::remove_dir_all(&test_folder).unwrap();
+    }
+
+    #[test]
+    pub fn test_rewrite_cache_name() {
+        assert_eq!(
+            rewrite_cache_name("// comment\nconst CACHE_NAME = '2024.501.223';\n", "ed7002b439e9ac84").unwrap(),
+            "// comment\nconst CACHE_NAME = 'ed7002b439e9ac84';\n"
+        );
+        assert_eq!(rewrite_cache_name("const VERSION = '1';", "x"), None);
+    }
+
+    #[test]
+    pub fn test_compress() {
+        let content = "fn main() {}\n".repeat(100);
+
+        let mut decompressed = String::new();
+        brotli::Decompressor::new(compress_brotli(content.as_bytes()).unwrap().as_slice(), 4096)
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, content);
+
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(compress_gzip(content.as_bytes()).unwrap().as_slice())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, content);
+    }
+}
@@ -0,0 +1,229 @@
+// rotate_secret_key_mod.rs
+
+//! Re-encrypt the stored secret tokens with a new SSH private key.
+//!
+//! Every `.enc` file in `~/.ssh` records in `private_key_file_name` the key that encrypted it.
+//! All the files encrypted with the old key are decrypted and encrypted again with the new key.
+//! The new encrypted text is decrypted once more with the new key to verify it.
+//! Only when all the files are verified, they replace the old files.
+//! The file `{old}.enc` becomes `{new}.enc` and the config files that name the old key are updated,
+//! so the tasks find the tokens without running the OAuth device flow again.
+
+use secrecy::{ExposeSecret, SecretBox, SecretString};
+
+use crate::cl;
+use crate::dry_run_mod as dr;
+use crate::encrypt_decrypt_with_ssh_key_mod::encrypt_decrypt_mod as ende;
+
+#[allow(unused_imports)]
+use cl::{BLUE, GREEN, RED, RESET, YELLOW};
+
+/// The config files and their field with the private key file name.
+const CONFIG_FILES_WITH_KEY_NAME: [(&str, &str); 2] = [
+    ("automation_tasks_rs/github_api_config.json", "github_api_private_key_file_name"),
+    ("automation_tasks_rs/crates_io_config.json", "crates_io_private_key_file_name"),
+];
+
+/// Signs the seed with the named private key into the passcode for symmetric encryption.
+type SignSeedFn = dyn Fn(&str, [u8; 32]) -> anyhow::Result<SecretBox<[u8; 32]>>;
+
+/// A re-encrypted and verified file, ready to be written.
+struct RotatedFile {
+    old_path: camino::Utf8PathBuf,
+    new_path: camino::Utf8PathBuf,
+    file_text: String,
+}
+
+/// Re-encrypt all the `.enc` files in `~/.ssh` from the old to the new private key.
+pub fn rotate_secret_key(old_private_key_file_name: &str, new_private_key_file_name: &str) -> anyhow::Result<()> {
+    crate::ci_mode_mod::error_if_ci_mode("Unlock the old and the new SSH private key.")?;
+    if old_private_key_file_name == new_private_key_file_name {
+        anyhow::bail!("The old and the new private key are the same: {old_private_key_file_name}.");
+    }
+    for private_key_file_name in [old_private_key_file_name, new_private_key_file_name] {
+        let private_key_path_struct = ende::PathStructInSshFolder::new(private_key_file_name.to_string())?;
+        if !private_key_path_struct.exists() {
+            anyhow::bail!("Private key {private_key_path_struct} does not exist.");
+        }
+    }
+    println!("  {YELLOW}Every file needs the old key once and the new key twice, to encrypt and to verify.{RESET}");
+    println!("  {YELLOW}Add both keys to ssh-agent to avoid typing the passphrases many times:{RESET}");
+    println!("{GREEN}ssh-add -t 1h ~/.ssh/{old_private_key_file_name} ~/.ssh/{new_private_key_file_name}{RESET}");
+
+    let ssh_folder = ende::tilde_expand_to_home_dir_utf8("~/.ssh")?;
+    let mut rotated_files = vec![];
+    for (path, encrypted_text_with_metadata) in crate::secrets_mod::encrypted_files_in_ssh_folder()? {
+        if encrypted_text_with_metadata.private_key_file_name != old_private_key_file_name {
+            continue;
+        }
+        println!("  {YELLOW}Re-encrypt and verify {path}{RESET}");
+        let encrypted_text_with_metadata = re_encrypt_with_metadata(
+            &encrypted_text_with_metadata,
+            new_private_key_file_name,
+            &sign_seed_with_private_key,
+        )?;
+        let plain_file_text = serde_json::to_string_pretty(&encrypted_text_with_metadata)?;
+        let file_name = path.file_name().unwrap_or_default();
+        let new_path = ssh_folder.join(rotated_file_name(file_name, old_private_key_file_name, new_private_key_file_name));
+        if new_path != path && new_path.exists() {
+            anyhow::bail!("The file {new_path} already exists. Move it away and rotate again.");
+        }
+        rotated_files.push(RotatedFile {
+            new_path,
+            old_path: path,
+            // encode it just to obscure it a little bit
+            file_text: ende::encode64_from_string_to_string(&plain_file_text),
+        });
+    }
+    if rotated_files.is_empty() {
+        println!("  {YELLOW}No encrypted file in {ssh_folder} uses the private key {old_private_key_file_name}.{RESET}");
+        return Ok(());
+    }
+
+    // all the files are verified, only now replace them
+    for rotated_file in rotated_files.iter() {
+        if dr::skip(&format!("write {} and remove {}", rotated_file.new_path, rotated_file.old_path)) {
+            continue;
+        }
+        write_secret_file(&rotated_file.new_path, &rotated_file.file_text)?;
+        if rotated_file.new_path != rotated_file.old_path {
+            std::fs::remove_file(&rotated_file.old_path)?;
+        }
+        println!("  {YELLOW}Encrypted with the new key: {}{RESET}", rotated_file.new_path);
+    }
+    update_config_files(old_private_key_file_name, new_private_key_file_name)?;
+    Ok(())
+}
+
+/// Sign the seed with the private key from ssh-agent or from the file in `~/.ssh`.
+fn sign_seed_with_private_key(private_key_file_name: &str, plain_seed_bytes_32bytes: [u8; 32]) -> anyhow::Result<SecretBox<[u8; 32]>> {
+    let private_key_path_struct = ende::PathStructInSshFolder::new(private_key_file_name.to_string())?;
+    ende::sign_seed_with_ssh_agent_or_private_key_file(&private_key_path_struct, plain_seed_bytes_32bytes)
+}
+
+/// Decrypt with the old key, encrypt with the new key and verify by decrypting again.
+///
+/// The signing function is a parameter, so the tests don't need real SSH keys.
+/// The verification also proves that the new key signs the seed always the same way,
+/// else the file could never be decrypted again.
+fn re_encrypt_with_metadata(
+    encrypted_text_with_metadata: &ende::EncryptedTextWithMetadata,
+    new_private_key_file_name: &str,
+    sign_seed: &SignSeedFn,
+) -> anyhow::Result<ende::EncryptedTextWithMetadata> {
+    let old_plain_seed_bytes_32bytes = ende::decode64_from_string_to_32bytes(&encrypted_text_with_metadata.plain_seed_string)?;
+    let secret_passcode_32bytes = sign_seed(&encrypted_text_with_metadata.private_key_file_name, old_plain_seed_bytes_32bytes)?;
+    let secret_string = ende::decrypt_symmetric(secret_passcode_32bytes, encrypted_text_with_metadata.plain_encrypted_text.clone())?;
+
+    let (plain_seed_bytes_32bytes, plain_seed_string) = ende::random_seed_32bytes_and_string()?;
+    let secret_passcode_32bytes = sign_seed(new_private_key_file_name, plain_seed_bytes_32bytes)?;
+    let plain_encrypted_text = ende::encrypt_symmetric(secret_passcode_32bytes, SecretString::from(secret_string.expose_secret()))?;
+
+    let secret_passcode_32bytes = sign_seed(new_private_key_file_name, plain_seed_bytes_32bytes)?;
+    let secret_verified_string = ende::decrypt_symmetric(secret_passcode_32bytes, plain_encrypted_text.clone())?;
+    if secret_verified_string.expose_secret() != secret_string.expose_secret() {
+        anyhow::bail!("Verification failed: the secret encrypted with {new_private_key_file_name} is different.");
+    }
+
+    Ok(ende::EncryptedTextWithMetadata {
+        private_key_file_name: new_private_key_file_name.to_string(),
+        plain_seed_string,
+        plain_encrypted_text,
+        token_name: encrypted_text_with_metadata.token_name.clone(),
+        access_token_expiration: encrypted_text_with_metadata.access_token_expiration.clone(),
+        refresh_token_expiration: encrypted_text_with_metadata.refresh_token_expiration.clone(),
+    })
+}
+
+/// The tasks find the file by the key name `{private_key_file_name}.enc`. Other file names stay the same.
+fn rotated_file_name(file_name: &str, old_private_key_file_name: &str, new_private_key_file_name: &str) -> String {
+    if file_name == format!("{old_private_key_file_name}.enc") {
+        format!("{new_private_key_file_name}.enc")
+    } else {
+        file_name.to_string()
+    }
+}
+
+/// Write into a temporary file readable only by the owner and then rename it over the file.
+fn write_secret_file(path: &camino::Utf8Path, file_text: &str) -> anyhow::Result<()> {
+    let tmp_path = camino::Utf8PathBuf::from(format!("{path}.tmp"));
+    std::fs::write(&tmp_path, file_text)?;
+    #[cfg(target_family = "unix")]
+    std::fs::set_permissions(&tmp_path, std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Replace the old private key file name in the config files.
+fn update_config_files(old_private_key_file_name: &str, new_private_key_file_name: &str) -> anyhow::Result<()> {
+    for (config_path, field_name) in CONFIG_FILES_WITH_KEY_NAME {
+        let Ok(config_json) = std::fs::read_to_string(config_path) else {
+            continue;
+        };
+        let mut config: serde_json::Value = serde_json::from_str(&config_json)?;
+        if config[field_name] != old_private_key_file_name {
+            continue;
+        }
+        config[field_name] = serde_json::Value::from(new_private_key_file_name);
+        if !dr::skip(&format!("write {config_path} with {field_name}: {new_private_key_file_name}")) {
+            std::fs::write(config_path, serde_json::to_string_pretty(&config)?)?;
+            println!("  {YELLOW}{config_path} uses the new key.{RESET}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A fake signature that depends on the key name and the seed, like a real deterministic signature.
+    fn fake_sign_seed(private_key_file_name: &str, plain_seed_bytes_32bytes: [u8; 32]) -> anyhow::Result<SecretBox<[u8; 32]>> {
+        let mut secret_passcode_32bytes = plain_seed_bytes_32bytes;
+        for (i, byte) in private_key_file_name.bytes().enumerate() {
+            secret_passcode_32bytes[i % 32] ^= byte;
+        }
+        Ok(SecretBox::new(Box::new(secret_passcode_32bytes)))
+    }
+
+    #[test]
+    pub fn test_re_encrypt_with_metadata() {
+        let (plain_seed_bytes_32bytes, plain_seed_string) = ende::random_seed_32bytes_and_string().unwrap();
+        let plain_encrypted_text = ende::encrypt_symmetric(
+            fake_sign_seed("old_key", plain_seed_bytes_32bytes).unwrap(),
+            SecretString::from("crates_io_secret"),
+        )
+        .unwrap();
+        let encrypted_text_with_metadata = ende::EncryptedTextWithMetadata {
+            private_key_file_name: "old_key".to_string(),
+            plain_seed_string,
+            plain_encrypted_text,
+            token_name: Some("crates.io".to_string()),
+            access_token_expiration: None,
+            refresh_token_expiration: Some("2026-12-31T00:00:00+00:00".to_string()),
+        };
+
+        let rotated = re_encrypt_with_metadata(&encrypted_text_with_metadata, "new_key", &fake_sign_seed).unwrap();
+        assert_eq!(rotated.private_key_file_name, "new_key");
+        assert_ne!(rotated.plain_seed_string, encrypted_text_with_metadata.plain_seed_string);
+        assert_eq!(rotated.token_name.as_deref(), Some("crates.io"));
+        assert_eq!(
+            rotated.refresh_token_expiration,
+            encrypted_text_with_metadata.refresh_token_expiration
+        );
+
+        let plain_seed_bytes_32bytes = ende::decode64_from_string_to_32bytes(&rotated.plain_seed_string).unwrap();
+        let secret_string = ende::decrypt_symmetric(
+            fake_sign_seed("new_key", plain_seed_bytes_32bytes).unwrap(),
+            rotated.plain_encrypted_text,
+        )
+        .unwrap();
+        assert_eq!(secret_string.expose_secret(), "crates_io_secret");
+    }
+
+    #[test]
+    pub fn test_rotated_file_name() {
+        assert_eq!(rotated_file_name("old_key.enc", "old_key", "new_key"), "new_key.enc");
+        assert_eq!(rotated_file_name("other_token.enc", "old_key", "new_key"), "other_token.enc");
+    }
+}
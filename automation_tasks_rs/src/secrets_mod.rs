@@ -0,0 +1,262 @@
+// secrets_mod.rs
+
+//! Inventory of the encrypted secret tokens in `~/.ssh`.
+//!
+//! The metadata of the `.enc` files is in plain text, so the list is shown without decrypting anything.
+//! For every file it shows the purpose, the private key file that encrypted it and the expiry state.
+//! Then it offers to delete the expired files and to force a refresh of the GitHub OAuth tokens.
+//! The refresh only marks the access token as expired in the metadata,
+//! then github_api_token_with_oauth2_mod refreshes the tokens like on every expiration.
+
+use crate::cl;
+use crate::dry_run_mod as dr;
+use crate::encrypt_decrypt_with_ssh_key_mod::crates_io_api_token_mod::CRATES_IO_CONFIG;
+use crate::encrypt_decrypt_with_ssh_key_mod::encrypt_decrypt_mod as ende;
+use crate::encrypt_decrypt_with_ssh_key_mod::github_api_token_with_oauth2_mod as github_token;
+use crate::encrypt_decrypt_with_ssh_key_mod::github_api_token_with_oauth2_mod::GITHUB_API_CONFIG;
+
+#[allow(unused_imports)]
+use cl::{BLUE, GREEN, RED, RESET, YELLOW};
+
+/// The expiry state from the metadata of the encrypted file.
+#[derive(Debug, PartialEq)]
+enum ExpiryState {
+    /// Tokens like the crates.io token don't record the expiration.
+    NoExpiration,
+    Valid {
+        expires: chrono::DateTime<chrono::FixedOffset>,
+    },
+    /// The next use will get a new access token with the refresh token.
+    AccessTokenExpired {
+        refresh_expires: chrono::DateTime<chrono::FixedOffset>,
+    },
+    /// The token cannot be used or refreshed anymore.
+    Expired {
+        expired: chrono::DateTime<chrono::FixedOffset>,
+    },
+}
+
+impl std::fmt::Display for ExpiryState {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ExpiryState::NoExpiration => write!(f, "{YELLOW}no expiration recorded{RESET}"),
+            ExpiryState::Valid { expires } => write!(f, "{GREEN}valid until {}{RESET}", expires.to_rfc3339()),
+            ExpiryState::AccessTokenExpired { refresh_expires } => write!(
+                f,
+                "{YELLOW}access token expired, refresh possible until {}{RESET}",
+                refresh_expires.to_rfc3339()
+            ),
+            ExpiryState::Expired { expired } => write!(f, "{RED}expired on {}{RESET}", expired.to_rfc3339()),
+        }
+    }
+}
+
+/// List the encrypted files, then offer to delete the expired ones and to refresh the GitHub tokens.
+pub fn secrets() -> anyhow::Result<()> {
+    let github_file_name = format!("{}.enc", GITHUB_API_CONFIG.get().unwrap().github_api_private_key_file_name);
+    let crates_io_file_name = format!("{}.enc", CRATES_IO_CONFIG.get().unwrap().crates_io_private_key_file_name);
+    let utc_now = chrono::Utc::now().fixed_offset();
+
+    let encrypted_files = encrypted_files_in_ssh_folder()?;
+    if encrypted_files.is_empty() {
+        println!("  {YELLOW}There are no encrypted token files in ~/.ssh.{RESET}");
+        return Ok(());
+    }
+    let mut expired_files = vec![];
+    let mut github_refreshable_file = None;
+    for (path, encrypted_text_with_metadata) in encrypted_files.iter() {
+        let file_name = path.file_name().unwrap_or_default();
+        let purpose = purpose(file_name, encrypted_text_with_metadata, &github_file_name, &crates_io_file_name);
+        let expiry_state = expiry_state(encrypted_text_with_metadata, utc_now);
+        println!("{GREEN}~/.ssh/{file_name}{RESET}");
+        println!("  {YELLOW}purpose: {purpose}{RESET}");
+        println!(
+            "  {YELLOW}key file: ~/.ssh/{}{RESET}",
+            encrypted_text_with_metadata.private_key_file_name
+        );
+        println!("  {YELLOW}state: {expiry_state}{RESET}");
+        match expiry_state {
+            ExpiryState::Expired { .. } => expired_files.push(path),
+            ExpiryState::Valid { .. } | ExpiryState::AccessTokenExpired { .. } if file_name == github_file_name => {
+                github_refreshable_file = Some(path)
+            }
+            _ => {}
+        }
+    }
+
+    // in CI mode nobody can answer, so only the list is shown
+    if crate::ci_mode_mod::is_ci_mode() {
+        return Ok(());
+    }
+    for path in expired_files {
+        if confirm(&format!("Delete the expired file {path}?")) && !dr::skip(&format!("remove {path}")) {
+            std::fs::remove_file(path)?;
+            println!("  {YELLOW}Deleted {path}.{RESET}");
+        }
+    }
+    if let Some(path) = github_refreshable_file {
+        if confirm("Force a refresh of the GitHub OAuth tokens now?") && !dr::skip("refresh the GitHub OAuth tokens") {
+            force_refresh_github_tokens(path)?;
+            println!("  {YELLOW}The GitHub OAuth tokens are refreshed.{RESET}");
+        }
+    }
+    Ok(())
+}
+
+/// All the encrypted files in `~/.ssh` with their metadata, sorted by file name.
+///
+/// Other programs can have `.enc` files in `~/.ssh`, they are ignored.
+/// Nothing is decrypted, the metadata is in plain text.
+pub(crate) fn encrypted_files_in_ssh_folder() -> anyhow::Result<Vec<(camino::Utf8PathBuf, ende::EncryptedTextWithMetadata)>> {
+    let ssh_folder = ende::tilde_expand_to_home_dir_utf8("~/.ssh")?;
+    let mut encrypted_files = vec![];
+    for entry in std::fs::read_dir(&ssh_folder)? {
+        let Ok(path) = camino::Utf8PathBuf::try_from(entry?.path()) else {
+            continue;
+        };
+        if path.extension() != Some("enc") {
+            continue;
+        }
+        let Ok(plain_file_text) = ende::open_file_b64_get_string(&path) else {
+            continue;
+        };
+        let Ok(encrypted_text_with_metadata) = serde_json::from_str::<ende::EncryptedTextWithMetadata>(&plain_file_text) else {
+            continue;
+        };
+        encrypted_files.push((path, encrypted_text_with_metadata));
+    }
+    encrypted_files.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(encrypted_files)
+}
+
+/// Mark the access token as expired in the plain text metadata and get the secret token.
+///
+/// Like on every expiration, get_github_secret_token() uses the refresh token
+/// and saves the new access_token and refresh_token into the same file.
+fn force_refresh_github_tokens(path: &camino::Utf8Path) -> anyhow::Result<()> {
+    crate::ci_mode_mod::error_if_ci_mode("Unlock the SSH private key to refresh the GitHub OAuth tokens.")?;
+    let plain_file_text = ende::open_file_b64_get_string(path)?;
+    let mut encrypted_text_with_metadata: ende::EncryptedTextWithMetadata = serde_json::from_str(&plain_file_text)?;
+    encrypted_text_with_metadata.access_token_expiration = Some(chrono::Utc::now().to_rfc3339());
+    let plain_file_text = serde_json::to_string_pretty(&encrypted_text_with_metadata)?;
+    // encode it just to obscure it a little bit, like github_api_token_with_oauth2_mod
+    std::fs::write(path, ende::encode64_from_string_to_string(&plain_file_text))?;
+    github_token::get_github_secret_token()?;
+    Ok(())
+}
+
+/// Without terminal or in CI mode the answer is no.
+fn confirm(question: &str) -> bool {
+    if crate::ci_mode_mod::is_ci_mode() {
+        return false;
+    }
+    inquire::Confirm::new(question).with_default(false).prompt().unwrap_or(false)
+}
+
+/// The config files know the purpose of their token files, the others show token_name if it exists.
+fn purpose(
+    file_name: &str,
+    encrypted_text_with_metadata: &ende::EncryptedTextWithMetadata,
+    github_file_name: &str,
+    crates_io_file_name: &str,
+) -> String {
+    if file_name == github_file_name {
+        "GitHub API OAuth2 tokens".to_string()
+    } else if file_name == crates_io_file_name {
+        "crates.io publish token".to_string()
+    } else if let Some(token_name) = &encrypted_text_with_metadata.token_name {
+        token_name.to_string()
+    } else {
+        "unknown".to_string()
+    }
+}
+
+/// The refresh token expiration decides if the token is usable at all.
+fn expiry_state(
+    encrypted_text_with_metadata: &ende::EncryptedTextWithMetadata,
+    utc_now: chrono::DateTime<chrono::FixedOffset>,
+) -> ExpiryState {
+    let parse = |expiration: &Option<String>| {
+        expiration
+            .as_deref()
+            .and_then(|expiration| chrono::DateTime::parse_from_rfc3339(expiration).ok())
+    };
+    let access_token_expiration = parse(&encrypted_text_with_metadata.access_token_expiration);
+    let refresh_token_expiration = parse(&encrypted_text_with_metadata.refresh_token_expiration);
+    match (access_token_expiration, refresh_token_expiration) {
+        (_, Some(refresh_expires)) if refresh_expires <= utc_now => ExpiryState::Expired { expired: refresh_expires },
+        (Some(expires), Some(refresh_expires)) if expires <= utc_now => ExpiryState::AccessTokenExpired { refresh_expires },
+        (Some(expires), None) if expires <= utc_now => ExpiryState::Expired { expired: expires },
+        (Some(expires), _) => ExpiryState::Valid { expires },
+        (None, Some(refresh_expires)) => ExpiryState::Valid { expires: refresh_expires },
+        (None, None) => ExpiryState::NoExpiration,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn metadata(access_token_expiration: Option<&str>, refresh_token_expiration: Option<&str>) -> ende::EncryptedTextWithMetadata {
+        ende::EncryptedTextWithMetadata {
+            private_key_file_name: "github_api_ssh_1".to_string(),
+            plain_seed_string: String::new(),
+            plain_encrypted_text: String::new(),
+            token_name: None,
+            access_token_expiration: access_token_expiration.map(|x| x.to_string()),
+            refresh_token_expiration: refresh_token_expiration.map(|x| x.to_string()),
+        }
+    }
+
+    #[test]
+    pub fn test_expiry_state() {
+        let utc_now = chrono::DateTime::parse_from_rfc3339("2026-06-01T00:00:00+00:00").unwrap();
+        let before = "2026-05-01T00:00:00+00:00";
+        let after = "2026-07-01T00:00:00+00:00";
+        let date = |x| chrono::DateTime::parse_from_rfc3339(x).unwrap();
+
+        assert_eq!(expiry_state(&metadata(None, None), utc_now), ExpiryState::NoExpiration);
+        assert_eq!(
+            expiry_state(&metadata(Some(after), Some(after)), utc_now),
+            ExpiryState::Valid { expires: date(after) }
+        );
+        assert_eq!(
+            expiry_state(&metadata(Some(before), Some(after)), utc_now),
+            ExpiryState::AccessTokenExpired {
+                refresh_expires: date(after)
+            }
+        );
+        assert_eq!(
+            expiry_state(&metadata(Some(before), Some(before)), utc_now),
+            ExpiryState::Expired { expired: date(before) }
+        );
+        assert_eq!(
+            expiry_state(&metadata(Some(before), None), utc_now),
+            ExpiryState::Expired { expired: date(before) }
+        );
+        // an unreadable date is like a missing date
+        assert_eq!(expiry_state(&metadata(Some("never"), None), utc_now), ExpiryState::NoExpiration);
+    }
+
+    #[test]
+    pub fn test_purpose() {
+        let mut encrypted_text_with_metadata = metadata(None, None);
+        assert_eq!(
+            purpose("github_1.enc", &encrypted_text_with_metadata, "github_1.enc", "crates_1.enc"),
+            "GitHub API OAuth2 tokens"
+        );
+        assert_eq!(
+            purpose("crates_1.enc", &encrypted_text_with_metadata, "github_1.enc", "crates_1.enc"),
+            "crates.io publish token"
+        );
+        assert_eq!(
+            purpose("other.enc", &encrypted_text_with_metadata, "github_1.enc", "crates_1.enc"),
+            "unknown"
+        );
+        encrypted_text_with_metadata.token_name = Some("docker hub".to_string());
+        assert_eq!(
+            purpose("other.enc", &encrypted_text_with_metadata, "github_1.enc", "crates_1.enc"),
+            "docker hub"
+        );
+    }
+}
@@ -0,0 +1,269 @@
+// serve_mod.rs
+
+//! Development web server with live reload.
+//!
+//! It serves the `web_server_folder` with the correct MIME types and without any caching.
+//! It watches the `src/` folder, runs `wasm-pack build` and copies `pkg/` on changes and then reloads the page.
+//! This fast rebuild does not increment the version and does not run clippy like `cargo auto build`.
+//! The service worker is bypassed, otherwise its cache-first fetch handler would serve the old files after the rebuild.
+//! Instead of `start_service_worker.js` a script is served that unregisters it.
+//! Instead of `service_worker.js` a worker is served that deletes the caches and unregisters itself,
+//! because the browser checks for a new service worker even when the old one is still installed.
+//! The reload is pushed to the page over Server-Sent Events (SSE), a small script is injected into every html file.
+//! Only the standard library is used, because this is a simple server for one developer.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use crate::cl::ShellCommandLimitedDoubleQuotesSanitizerTrait;
+#[allow(unused_imports)]
+use crate::cl::{BLUE, GREEN, RED, RESET, YELLOW};
+use crate::dry_run_mod as dr;
+
+/// The url of the SSE channel for live reload.
+const LIVE_RELOAD_PATH: &str = "/__live_reload";
+
+/// The script injected before `</body>` in every html file.
+const LIVE_RELOAD_SCRIPT: &str = r#"<script>new EventSource("/__live_reload").onmessage = () => location.reload();</script>"#;
+
+/// Served instead of `start_service_worker.js`, so no service worker caches the files in serve mode.
+const UNREGISTER_SERVICE_WORKER_SCRIPT: &str = r#"if ('serviceWorker' in navigator) {
+    navigator.serviceWorker.getRegistrations().then((registrations) => registrations.forEach((registration) => registration.unregister()));
+}
+"#;
+
+/// Served instead of `service_worker.js`. It replaces the installed service worker and removes it with its caches.
+const SELF_REMOVING_SERVICE_WORKER_SCRIPT: &str = r#"self.addEventListener('install', () => self.skipWaiting());
+self.addEventListener('activate', (event) => {
+    event.waitUntil(caches.keys()
+        .then((keys) => Promise.all(keys.map((key) => caches.delete(key))))
+        .then(() => self.registration.unregister()));
+});
+"#;
+
+/// How often the src/ folder is checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The open SSE connections that will receive the reload message.
+type LiveReloadClients = Arc<Mutex<Vec<TcpStream>>>;
+
+/// Serve the folder on the port, watch src/ and push live reload to the browser.
+/// This function never returns. Stop it with ctrl+c.
+pub fn serve(web_server_folder: &Path, package_name: &str, port: u16) {
+    let listener = TcpListener::bind(("0.0.0.0", port)).unwrap_or_else(|e| panic!("{RED}Cannot listen on port {port}: {e}{RESET}"));
+    let clients: LiveReloadClients = Arc::new(Mutex::new(Vec::new()));
+
+    let watch_clients = clients.clone();
+    let package_name = package_name.to_string();
+    std::thread::spawn(move || watch_and_rebuild(Path::new("src"), &package_name, &watch_clients));
+
+    println!("  {YELLOW}Serving {} on{RESET}", web_server_folder.display());
+    println!("{GREEN}http://localhost:{port}/{RESET}");
+    println!("  {YELLOW}Watching src/ for changes. Stop the server with ctrl+c.{RESET}");
+    for stream in listener.incoming().flatten() {
+        let web_server_folder = web_server_folder.to_path_buf();
+        let clients = clients.clone();
+        std::thread::spawn(move || {
+            if let Err(err) = handle_connection(stream, &web_server_folder, &clients) {
+                tracing::debug!("connection error: {err}");
+            }
+        });
+    }
+}
+
+/// Poll the modification times. On change rebuild and send the reload message.
+fn watch_and_rebuild(watch_folder: &Path, package_name: &str, clients: &LiveReloadClients) {
+    let mut last_modified = latest_modified(watch_folder);
+    loop {
+        std::thread::sleep(WATCH_INTERVAL);
+        let modified = latest_modified(watch_folder);
+        if modified == last_modified {
+            continue;
+        }
+        println!("  {YELLOW}Change detected in {}. Rebuilding...{RESET}", watch_folder.display());
+        match rebuild(package_name) {
+            Ok(()) => {
+                println!("  {GREEN}Rebuild finished. Reloading the page.{RESET}");
+                send_reload(clients);
+            }
+            Err(err) => eprintln!("{RED}Rebuild failed: {err}{RESET}"),
+        }
+        last_modified = latest_modified(watch_folder);
+    }
+}
+
+/// The same wasm-pack build and copy of pkg/ as `cargo auto build`, without version increment, fmt and clippy.
+/// A separate process, so a compile error does not stop the server.
+fn rebuild(package_name: &str) -> anyhow::Result<()> {
+    dr::run_shell_command_static("wasm-pack build --target web --profiling")?;
+    dr::ShellCommand::new(r#"rsync -a --delete-after pkg/ "web_server_folder/{package_name}/pkg/" "#)?
+        .arg("{package_name}", package_name)?
+        .run()?;
+    Ok(())
+}
+
+/// The newest modification time of all files in the folder and subfolders.
+fn latest_modified(folder: &Path) -> Option<SystemTime> {
+    let mut latest = None;
+    let Ok(entries) = std::fs::read_dir(folder) else {
+        return latest;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let modified = if path.is_dir() {
+            latest_modified(&path)
+        } else {
+            entry.metadata().and_then(|metadata| metadata.modified()).ok()
+        };
+        latest = latest.max(modified);
+    }
+    latest
+}
+
+/// Send the SSE message to all open pages and forget the closed connections.
+fn send_reload(clients: &LiveReloadClients) {
+    let mut clients = clients.lock().unwrap_or_else(|e| e.into_inner());
+    clients.retain_mut(|stream| stream.write_all(b"data: reload\n\n").and_then(|()| stream.flush()).is_ok());
+}
+
+/// Read one request and write the response. Only GET and HEAD are supported.
+fn handle_connection(mut stream: TcpStream, web_server_folder: &Path, clients: &LiveReloadClients) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // the headers are not needed
+    let mut header_line = String::new();
+    while reader.read_line(&mut header_line)? > 2 {
+        header_line.clear();
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let url_path = parts.next().unwrap_or("/");
+    let url_path = url_path.split(['?', '#']).next().unwrap_or("/");
+    tracing::debug!("{method} {url_path}");
+
+    if method != "GET" && method != "HEAD" {
+        return write_response(&mut stream, "405 Method Not Allowed", "text/plain", b"Method Not Allowed");
+    }
+    if url_path == LIVE_RELOAD_PATH {
+        stream.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\nConnection: keep-alive\r\n\r\n",
+        )?;
+        stream.flush()?;
+        clients.lock().unwrap_or_else(|e| e.into_inner()).push(stream);
+        return Ok(());
+    }
+    let Some(file_path) = resolve_file_path(web_server_folder, url_path) else {
+        return write_response(&mut stream, "404 Not Found", "text/plain", b"Not Found");
+    };
+    if file_path.is_dir() {
+        // relative urls inside index.html need the trailing slash
+        let response =
+            format!("HTTP/1.1 301 Moved Permanently\r\nLocation: {url_path}/\r\nContent-Length: 0\r\nCache-Control: no-store\r\n\r\n");
+        return stream.write_all(response.as_bytes());
+    }
+    let body = match file_path.file_name().and_then(|file_name| file_name.to_str()) {
+        Some("start_service_worker.js") => Ok(UNREGISTER_SERVICE_WORKER_SCRIPT.as_bytes().to_vec()),
+        Some("service_worker.js") => Ok(SELF_REMOVING_SERVICE_WORKER_SCRIPT.as_bytes().to_vec()),
+        _ => std::fs::read(&file_path),
+    };
+    let Ok(mut body) = body else {
+        return write_response(&mut stream, "404 Not Found", "text/plain", b"Not Found");
+    };
+    let content_type = content_type(&file_path);
+    if content_type.starts_with("text/html") {
+        body = inject_live_reload_script(&String::from_utf8_lossy(&body)).into_bytes();
+    }
+    if method == "HEAD" {
+        body.clear();
+    }
+    write_response(&mut stream, "200 OK", content_type, &body)
+}
+
+/// write the status, headers and body
+/// The browser must never cache, because the files change with every rebuild.
+fn write_response(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) -> std::io::Result<()> {
+    let headers = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(headers.as_bytes())?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+/// Map the url path to a file inside the folder. Returns None for paths that try to escape the folder.
+/// The folder path `/` is mapped to `index.html`.
+fn resolve_file_path(web_server_folder: &Path, url_path: &str) -> Option<PathBuf> {
+    let mut file_path = web_server_folder.to_path_buf();
+    for segment in url_path.split('/').filter(|segment| !segment.is_empty()) {
+        if segment == ".." || segment == "." || segment.contains('\\') {
+            return None;
+        }
+        file_path.push(segment);
+    }
+    if url_path.ends_with('/') {
+        file_path.push("index.html");
+    }
+    Some(file_path)
+}
+
+/// The MIME type from the file extension. Wasm must be `application/wasm` for streaming compilation.
+fn content_type(file_path: &Path) -> &'static str {
+    match file_path.extension().and_then(|extension| extension.to_str()).unwrap_or_default() {
+        "html" => "text/html; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "json" => "application/json",
+        "webmanifest" => "application/manifest+json",
+        "wasm" => "application/wasm",
+        "png" => "image/png",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "woff2" => "font/woff2",
+        "txt" | "md" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Insert the live reload script before `</body>` or at the end.
+fn inject_live_reload_script(html: &str) -> String {
+    match html.rfind("</body>") {
+        Some(position) => format!("{}{LIVE_RELOAD_SCRIPT}\n{}", &html[..position], &html[position..]),
+        None => format!("{html}{LIVE_RELOAD_SCRIPT}"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_resolve_file_path() {
+        let folder = Path::new("web_server_folder");
+        assert_eq!(
+            resolve_file_path(folder, "/snake_bevy_wasm_pwa/"),
+            Some(PathBuf::from("web_server_folder/snake_bevy_wasm_pwa/index.html"))
+        );
+        assert_eq!(
+            resolve_file_path(folder, "/snake_bevy_wasm_pwa/pkg/snake_bevy_wasm_pwa_bg.wasm"),
+            Some(PathBuf::from(
+                "web_server_folder/snake_bevy_wasm_pwa/pkg/snake_bevy_wasm_pwa_bg.wasm"
+            ))
+        );
+        assert_eq!(resolve_file_path(folder, "/../Cargo.toml"), None);
+    }
+
+    #[test]
+    pub fn test_content_type_and_live_reload_script() {
+        assert_eq!(content_type(Path::new("pkg/snake_bevy_wasm_pwa_bg.wasm")), "application/wasm");
+        assert_eq!(content_type(Path::new("service_worker.js")), "text/javascript; charset=utf-8");
+        assert_eq!(
+            inject_live_reload_script("<body>x</body></html>"),
+            format!("<body>x{LIVE_RELOAD_SCRIPT}\n</body></html>")
+        );
+    }
+}
@@ -0,0 +1,158 @@
+// ship_mod.rs
+
+//! Run the whole release workflow in one task with resumable state.
+//!
+//! `cargo auto ship "message"` runs the steps version_increment, build, release, doc, test, commit_and_push,
+//! publish_to_web and github_new_release one after the other.
+//! Every completed step is recorded in the state file together with the version from Cargo.toml.
+//! When a step fails, the next `cargo auto ship` resumes from the failed step.
+//! The version is incremented only in its own step, so a failed build does not increment it again.
+//! After the last step the state file is removed.
+
+use crate::cl;
+use crate::dry_run_mod as dr;
+
+#[allow(unused_imports)]
+use cl::{BLUE, GREEN, RED, RESET, YELLOW};
+
+/// The state file in the project root directory. It is in .gitignore.
+pub const SHIP_STATE_FILE: &str = ".ship_state.json";
+
+/// A named step of the workflow. The tasks panic on errors.
+pub type ShipStep<'a> = (&'static str, &'a dyn Fn());
+
+/// The progress of the unfinished ship.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Default, PartialEq)]
+struct ShipState {
+    message: String,
+    /// The version after the last completed step.
+    version: String,
+    completed_steps: Vec<String>,
+    failed_step: Option<String>,
+}
+
+/// Run the steps that are not yet completed and record the progress in the state file.
+pub fn ship(message: &str, steps: &[ShipStep], state_path: &camino::Utf8Path, current_version: &dyn Fn() -> String) -> anyhow::Result<()> {
+    let mut ship_state = read_ship_state(state_path)?;
+    if ship_state.completed_steps.is_empty() {
+        ship_state.message = message.to_string();
+    } else {
+        println!(
+            "  {YELLOW}Resume the unfinished ship of version {} from {state_path}.{RESET}",
+            ship_state.version
+        );
+        println!("  {YELLOW}Completed steps: {}{RESET}", ship_state.completed_steps.join(", "));
+        if ship_state.message != message {
+            println!(
+                "  {YELLOW}The commit message changed from `{}` to `{message}`.{RESET}",
+                ship_state.message
+            );
+            ship_state.message = message.to_string();
+        }
+        let version = current_version();
+        if version != ship_state.version {
+            println!(
+                "  {YELLOW}Cargo.toml has the version {version}, but the completed steps used {}.{RESET}",
+                ship_state.version
+            );
+        }
+    }
+
+    for (step_name, run_step) in steps {
+        if ship_state.completed_steps.iter().any(|completed| completed == step_name) {
+            println!("  {YELLOW}Skip the completed step: {step_name}{RESET}");
+            continue;
+        }
+        println!("  {YELLOW}Ship step: {step_name}{RESET}");
+        // the panic hook already printed the error of the task
+        if std::panic::catch_unwind(std::panic::AssertUnwindSafe(run_step)).is_err() {
+            ship_state.failed_step = Some(step_name.to_string());
+            write_ship_state(state_path, &ship_state)?;
+            anyhow::bail!(
+                "The ship step {step_name} failed. Fix the problem and run `cargo auto ship \"message\"` to resume from {step_name}.\nDelete {state_path} to ship from the beginning."
+            );
+        }
+        ship_state.completed_steps.push(step_name.to_string());
+        ship_state.failed_step = None;
+        ship_state.version = current_version();
+        write_ship_state(state_path, &ship_state)?;
+    }
+
+    if state_path.exists() && !dr::skip(&format!("remove {state_path}")) {
+        std::fs::remove_file(state_path)?;
+    }
+    println!("  {GREEN}Shipped version {}.{RESET}", ship_state.version);
+    Ok(())
+}
+
+/// Without state file all the steps are still to do.
+fn read_ship_state(state_path: &camino::Utf8Path) -> anyhow::Result<ShipState> {
+    if !state_path.exists() {
+        return Ok(ShipState::default());
+    }
+    let state_json = std::fs::read_to_string(state_path)?;
+    serde_json::from_str(&state_json)
+        .map_err(|err| anyhow::anyhow!("The state file {state_path} is not correct: {err}. Delete it to ship from the beginning."))
+}
+
+/// In dry-run nothing really happened, so there is nothing to resume.
+fn write_ship_state(state_path: &camino::Utf8Path, ship_state: &ShipState) -> anyhow::Result<()> {
+    if dr::is_dry_run() {
+        return Ok(());
+    }
+    std::fs::write(state_path, serde_json::to_string_pretty(ship_state)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    #[test]
+    pub fn test_ship_resumes_from_failed_step() {
+        let state_path =
+            camino::Utf8PathBuf::from_path_buf(std::env::temp_dir().join(format!("test_ship_state_{}.json", std::process::id()))).unwrap();
+        let version = RefCell::new("1.0.0".to_string());
+        let runs = RefCell::new(vec![]);
+        let build_fails = Cell::new(true);
+
+        let version_increment = || {
+            runs.borrow_mut().push("version_increment");
+            *version.borrow_mut() = "1.0.1".to_string();
+        };
+        let build = || {
+            runs.borrow_mut().push("build");
+            if build_fails.get() {
+                panic!("build failed");
+            }
+        };
+        let publish = || runs.borrow_mut().push("publish_to_web");
+        let steps: [ShipStep; 3] = [
+            ("version_increment", &version_increment),
+            ("build", &build),
+            ("publish_to_web", &publish),
+        ];
+        let current_version = || version.borrow().clone();
+
+        let err = ship("first message", &steps, &state_path, &current_version).unwrap_err();
+        assert!(err.to_string().contains("resume from build"));
+        let ship_state = read_ship_state(&state_path).unwrap();
+        assert_eq!(
+            ship_state,
+            ShipState {
+                message: "first message".to_string(),
+                version: "1.0.1".to_string(),
+                completed_steps: vec!["version_increment".to_string()],
+                failed_step: Some("build".to_string()),
+            }
+        );
+
+        build_fails.set(false);
+        ship("second message", &steps, &state_path, &current_version).unwrap();
+        // version_increment did not run again after the failed build, so the version was incremented only once
+        assert_eq!(*runs.borrow(), vec!["version_increment", "build", "build", "publish_to_web"]);
+        assert_eq!(*version.borrow(), "1.0.1");
+        assert!(!state_path.exists());
+    }
+}
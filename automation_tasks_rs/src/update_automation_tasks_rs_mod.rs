@@ -0,0 +1,319 @@
+// update_automation_tasks_rs_mod.rs
+
+//! Update the generic files of automation_tasks_rs from a chosen source.
+//!
+//! The source is a local folder or a git url of a project that also uses automation_tasks_rs.
+//! Only the files marked with "Don't change this code" are updated automatically, and `main.rs` only with confirmation.
+//! The hashes of the updated files are stored in `automation_tasks_rs/.automation_tasks_rs_file_hashes.json`.
+//! If the hash of a local file is different, the user customized it. Then the diff is shown and the update needs confirmation.
+//! The generic files of the tasks customized in main.rs are removed and not created again.
+//! With `--ci` nobody can confirm, so the update of a customized file fails.
+//! After the update `cargo check` runs. If it fails, the old files are restored.
+
+use crate::cl;
+use crate::dry_run_mod as dr;
+
+use cargo_auto_lib::ShellCommandLimitedDoubleQuotesSanitizerTrait;
+#[allow(unused_imports)]
+use cl::{BLUE, GREEN, RED, RESET, YELLOW};
+
+/// The files with this text can be updated automatically.
+const DONT_CHANGE_MARKER: &str = "Don't change this code, so it can be updated regularly with";
+
+/// The hashes of the files written by the last update.
+const FILE_HASHES_PATH: &str = "automation_tasks_rs/.automation_tasks_rs_file_hashes.json";
+
+/// The generic files removed from this project, because their tasks are customized in main.rs.
+const REMOVED_FILES: [&str; 2] = ["build_wasm_mod.rs", "tasks_mod.rs"];
+
+/// The git url source is cloned into this temporary folder.
+const CLONE_FOLDER: &str = "tmp/update_automation_tasks_rs_source";
+
+/// The customized files overwritten by the update are saved into this temporary folder.
+const BACKUP_FOLDER: &str = "tmp/update_automation_tasks_rs_backup";
+
+/// What to do with one file from the source.
+#[derive(Debug, PartialEq)]
+enum UpdateAction {
+    /// the file does not exist locally
+    Create,
+    /// the local file is the same as the source
+    Unchanged,
+    /// the local file was not customized since the last update
+    Overwrite,
+    /// the local file was customized or it is main.rs, show the diff and ask
+    Confirm,
+}
+
+/// Update the files in automation_tasks_rs/src from the source folder or git url.
+///
+/// After the update `cargo check` must succeed, else all the written files are restored.
+pub fn update_automation_tasks_rs(source: &str) -> anyhow::Result<()> {
+    let source_src_folder = source_src_folder(source)?;
+    println!("  {YELLOW}Update from {}{RESET}", source_src_folder.display());
+    let mut file_hashes = read_file_hashes();
+    let mut backups = vec![];
+    let result = write_updated_files(&source_src_folder, &mut file_hashes, &mut backups).and_then(|()| cargo_check(&backups));
+    if let Err(err) = result {
+        restore_backups(&backups)?;
+        let restored: Vec<&str> = backups.iter().map(|backup| backup.relative_path.as_str()).collect();
+        anyhow::bail!("{err}\nThe update was reverted. Restored files: {}", restored.join(", "));
+    }
+    if !dr::skip(&format!("write {FILE_HASHES_PATH}")) {
+        std::fs::write(FILE_HASHES_PATH, serde_json::to_string_pretty(&file_hashes)?)?;
+    }
+    save_lost_customizations(&backups)
+}
+
+/// The content of a local file before the update, to restore it if the update fails.
+struct Backup {
+    relative_path: String,
+    local_path: std::path::PathBuf,
+    /// None if the file did not exist before the update
+    old_text: Option<String>,
+    /// The user confirmed to overwrite the customized file.
+    is_customized: bool,
+}
+
+/// Write the files from the source and record a backup for every written file.
+fn write_updated_files(
+    source_src_folder: &std::path::Path,
+    file_hashes: &mut std::collections::BTreeMap<String, String>,
+    backups: &mut Vec<Backup>,
+) -> anyhow::Result<()> {
+    let local_src_folder = std::path::Path::new("automation_tasks_rs/src");
+    for source_path in files_recursive(source_src_folder)? {
+        let relative_path = source_path.strip_prefix(source_src_folder)?.to_string_lossy().to_string();
+        let source_text = std::fs::read_to_string(&source_path)?;
+        let is_main_rs = relative_path == "main.rs";
+        if (!is_main_rs && !source_text.contains(DONT_CHANGE_MARKER)) || REMOVED_FILES.contains(&relative_path.as_str()) {
+            continue;
+        }
+        let local_path = local_src_folder.join(&relative_path);
+        let local_text = std::fs::read_to_string(&local_path).ok();
+        let action = update_action(
+            local_text.as_deref(),
+            &source_text,
+            file_hashes.get(&relative_path).map(|hash| hash.as_str()),
+            is_main_rs,
+        );
+        let write = match action {
+            UpdateAction::Unchanged => {
+                // the same as the source, so it is not customized
+                file_hashes.insert(relative_path.clone(), cl::hash_text(&source_text));
+                false
+            }
+            UpdateAction::Create | UpdateAction::Overwrite => true,
+            UpdateAction::Confirm => {
+                println!("  {YELLOW}The file {relative_path} was customized. The difference to the source:{RESET}");
+                print_diff(&local_path, &source_path);
+                let question = format!("Overwrite {relative_path} with the source?");
+                // with --dry-run or without terminal the answer is no
+                if dr::is_dry_run() {
+                    false
+                } else {
+                    crate::ci_mode_mod::error_if_ci_mode(&question)?;
+                    inquire::Confirm::new(&question).with_default(false).prompt().unwrap_or(false)
+                }
+            }
+        };
+        println!(
+            "  {YELLOW}{relative_path}: {action:?}{}{RESET}",
+            if write { "" } else { ", not written" }
+        );
+        if write && !dr::skip(&format!("write {}", local_path.display())) {
+            backups.push(Backup {
+                relative_path: relative_path.clone(),
+                local_path: local_path.clone(),
+                old_text: local_text,
+                is_customized: action == UpdateAction::Confirm,
+            });
+            if let Some(parent) = local_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&local_path, &source_text)?;
+            file_hashes.insert(relative_path, cl::hash_text(&source_text));
+        }
+    }
+    Ok(())
+}
+
+/// The updated files must compile together with the local files.
+fn cargo_check(backups: &[Backup]) -> anyhow::Result<()> {
+    if backups.is_empty() {
+        return Ok(());
+    }
+    let status = std::process::Command::new("cargo")
+        .arg("check")
+        .current_dir("automation_tasks_rs")
+        .status()?;
+    if !status.success() {
+        anyhow::bail!("cargo check failed for the updated automation_tasks_rs.");
+    }
+    Ok(())
+}
+
+/// Restore the old content of all written files and remove the created files.
+///
+/// Returns an error with the customized files that could not be restored, because their customizations are lost.
+fn restore_backups(backups: &[Backup]) -> anyhow::Result<()> {
+    let mut lost = vec![];
+    for backup in backups.iter().rev() {
+        let result = match &backup.old_text {
+            Some(old_text) => std::fs::write(&backup.local_path, old_text),
+            None => std::fs::remove_file(&backup.local_path),
+        };
+        if let Err(err) = result {
+            eprintln!("{RED}Cannot restore {}: {err}{RESET}", backup.relative_path);
+            if backup.is_customized {
+                lost.push(backup.relative_path.as_str());
+            }
+        }
+    }
+    if !lost.is_empty() {
+        anyhow::bail!("The local customizations in these files are lost: {}", lost.join(", "));
+    }
+    Ok(())
+}
+
+/// The user confirmed to overwrite the customized files. Report them and keep the old files in BACKUP_FOLDER.
+fn save_lost_customizations(backups: &[Backup]) -> anyhow::Result<()> {
+    for backup in backups.iter().filter(|backup| backup.is_customized) {
+        let backup_path = std::path::Path::new(BACKUP_FOLDER).join(&backup.relative_path);
+        if let (Some(old_text), Some(parent)) = (&backup.old_text, backup_path.parent()) {
+            std::fs::create_dir_all(parent)?;
+            std::fs::write(&backup_path, old_text)?;
+        }
+        println!(
+            "  {YELLOW}The local customizations in {} were overwritten. The old file is in {}{RESET}",
+            backup.relative_path,
+            backup_path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Decide what to do with one file.
+/// The recorded hash is from the last update. Without it we cannot know if the file was customized.
+fn update_action(local_text: Option<&str>, source_text: &str, recorded_hash: Option<&str>, is_main_rs: bool) -> UpdateAction {
+    let Some(local_text) = local_text else {
+        return UpdateAction::Create;
+    };
+    if local_text == source_text {
+        return UpdateAction::Unchanged;
+    }
+    if !is_main_rs && recorded_hash == Some(cl::hash_text(local_text).as_str()) {
+        return UpdateAction::Overwrite;
+    }
+    // return
+    UpdateAction::Confirm
+}
+
+/// The src folder of automation_tasks_rs in the source. A git url is cloned first.
+fn source_src_folder(source: &str) -> anyhow::Result<std::path::PathBuf> {
+    let source_folder = if source.starts_with("https://") || source.starts_with("git@") {
+        let _ = std::fs::remove_dir_all(CLONE_FOLDER);
+        // cloning into tmp/ does not change the project, so it runs also with --dry-run
+        cl::ShellCommandLimitedDoubleQuotesSanitizer::new(r#"git clone --depth 1 "{source}" "{clone_folder}" "#)?
+            .arg("{source}", source)?
+            .arg("{clone_folder}", CLONE_FOLDER)?
+            .run()?;
+        std::path::PathBuf::from(CLONE_FOLDER)
+    } else {
+        std::path::PathBuf::from(source)
+    };
+    // the source can be the project root or the automation_tasks_rs folder itself
+    for src_folder in [source_folder.join("automation_tasks_rs/src"), source_folder.join("src")] {
+        if src_folder.join("main.rs").exists() {
+            return Ok(src_folder);
+        }
+    }
+    anyhow::bail!("The source {source} does not contain automation_tasks_rs/src/main.rs.")
+}
+
+/// All files in the folder and subfolders.
+fn files_recursive(folder: &std::path::Path) -> anyhow::Result<Vec<std::path::PathBuf>> {
+    let mut files = vec![];
+    for entry in std::fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(files_recursive(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// The hashes from the last update. Empty if the file does not exist yet.
+fn read_file_hashes() -> std::collections::BTreeMap<String, String> {
+    std::fs::read_to_string(FILE_HASHES_PATH)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Print the diff with git, because git is always installed.
+fn print_diff(local_path: &std::path::Path, source_path: &std::path::Path) {
+    match std::process::Command::new("git")
+        .args(["diff", "--no-index", "--color=always", "--"])
+        .arg(local_path)
+        .arg(source_path)
+        .output()
+    {
+        Ok(output) => println!("{}", String::from_utf8_lossy(&output.stdout)),
+        Err(err) => eprintln!("{RED}Cannot run git diff: {err}{RESET}"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_update_action() {
+        let hash = cl::hash_text("old");
+        assert_eq!(update_action(None, "new", None, false), UpdateAction::Create);
+        assert_eq!(update_action(Some("new"), "new", None, true), UpdateAction::Unchanged);
+        assert_eq!(update_action(Some("old"), "new", Some(&hash), false), UpdateAction::Overwrite);
+        // customized after the last update
+        assert_eq!(update_action(Some("customized"), "new", Some(&hash), false), UpdateAction::Confirm);
+        // never updated before, so we don't know
+        assert_eq!(update_action(Some("old"), "new", None, false), UpdateAction::Confirm);
+        // main.rs always needs confirmation
+        assert_eq!(update_action(Some("old"), "new", Some(&hash), true), UpdateAction::Confirm);
+    }
+
+    #[test]
+    pub fn test_restore_backups() {
+        let folder = std::env::temp_dir().join(format!("test_restore_backups_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let backups = vec![
+            Backup {
+                relative_path: "build_lib_mod.rs".to_string(),
+                local_path: folder.join("build_lib_mod.rs"),
+                old_text: Some("customized".to_string()),
+                is_customized: true,
+            },
+            Backup {
+                relative_path: "new_mod.rs".to_string(),
+                local_path: folder.join("new_mod.rs"),
+                old_text: None,
+                is_customized: false,
+            },
+        ];
+        for backup in backups.iter() {
+            std::fs::write(&backup.local_path, "from source").unwrap();
+        }
+
+        restore_backups(&backups).unwrap();
+        assert_eq!(std::fs::read_to_string(folder.join("build_lib_mod.rs")).unwrap(), "customized");
+        assert!(!folder.join("new_mod.rs").exists());
+
+        // the customized file cannot be restored into a missing folder
+        std::fs::remove_dir_all(&folder).unwrap();
+        let err = restore_backups(&backups[..1]).unwrap_err();
+        assert!(err.to_string().contains("customizations in these files are lost: build_lib_mod.rs"));
+    }
+}
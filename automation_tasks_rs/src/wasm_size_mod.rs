@@ -0,0 +1,366 @@
+// wasm_size_mod.rs
+
+//! Size report of the wasm bundle and the size budget for the release.
+//!
+//! Bevy makes the wasm file large, so every release reports the size of the sections
+//! and the largest functions when the wasm file has a name section.
+//! The size of every version is recorded in the tracked file `automation_tasks_rs/wasm_size_history.json`.
+//! The release fails when the wasm file grows more than `max_growth_percent` from `automation_tasks_rs/wasm_size_config.json`
+//! compared with the previous version. Then the size is not recorded, so the next release compares with the same version.
+
+use crate::cl;
+use crate::dry_run_mod as dr;
+
+#[allow(unused_imports)]
+use cl::{BLUE, GREEN, RED, RESET, YELLOW};
+
+const WASM_SIZE_CONFIG_PATH: &str = "automation_tasks_rs/wasm_size_config.json";
+const WASM_SIZE_HISTORY_PATH: &str = "automation_tasks_rs/wasm_size_history.json";
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct WasmSizeConfig {
+    /// The allowed growth of the wasm file compared with the previous version.
+    pub max_growth_percent: f64,
+    /// How many of the largest functions are reported.
+    pub largest_functions_count: usize,
+}
+
+/// The size of one version in the history file.
+#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+struct WasmSizeRecord {
+    version: String,
+    date: String,
+    total_bytes: u64,
+    sections: Vec<SectionSize>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, Clone)]
+struct SectionSize {
+    name: String,
+    bytes: u64,
+}
+
+/// The parsed sizes of the wasm file.
+#[derive(Debug, PartialEq)]
+struct WasmSizes {
+    total_bytes: u64,
+    sections: Vec<SectionSize>,
+    /// The code size of the functions from the name section, the largest first.
+    functions: Vec<(String, u64)>,
+}
+
+/// Report the sizes, check the budget and record the size of this version.
+pub fn wasm_size_report(wasm_path: &camino::Utf8Path, version: &str) -> anyhow::Result<()> {
+    if dr::skip(&format!("report the size of {wasm_path} and record it in {WASM_SIZE_HISTORY_PATH}")) {
+        return Ok(());
+    }
+    let wasm_size_config: WasmSizeConfig = serde_json::from_str(
+        &std::fs::read_to_string(WASM_SIZE_CONFIG_PATH).map_err(|_| anyhow::anyhow!("The file {WASM_SIZE_CONFIG_PATH} is missing."))?,
+    )
+    .map_err(|err| anyhow::anyhow!("The content of {WASM_SIZE_CONFIG_PATH} is not correct: {err}"))?;
+    let wasm_sizes = parse_wasm_sizes(&std::fs::read(wasm_path)?)?;
+
+    println!("  {YELLOW}Size of {wasm_path}: {}{RESET}", human_bytes(wasm_sizes.total_bytes));
+    for section in wasm_sizes.sections.iter() {
+        println!("  {YELLOW}{:>24} {:>12}{RESET}", section.name, human_bytes(section.bytes));
+    }
+    if wasm_sizes.functions.is_empty() {
+        println!("  {YELLOW}There is no name section with the function names, build with --profiling to see the largest functions.{RESET}");
+    } else {
+        println!("  {YELLOW}Largest functions:{RESET}");
+        for (name, bytes) in wasm_sizes.functions.iter().take(wasm_size_config.largest_functions_count) {
+            println!("  {YELLOW}{:>12} {name}{RESET}", human_bytes(*bytes));
+        }
+    }
+
+    let mut history: Vec<WasmSizeRecord> = match std::fs::read_to_string(WASM_SIZE_HISTORY_PATH) {
+        Ok(history_json) => serde_json::from_str(&history_json)
+            .map_err(|err| anyhow::anyhow!("The content of {WASM_SIZE_HISTORY_PATH} is not correct: {err}"))?,
+        Err(_) => vec![],
+    };
+    // a repeated release of the same version replaces its record
+    history.retain(|record| record.version != version);
+    if let Some(previous) = history.last() {
+        check_budget(previous, wasm_sizes.total_bytes, wasm_size_config.max_growth_percent)?;
+    }
+    history.push(WasmSizeRecord {
+        version: version.to_string(),
+        date: chrono::Utc::now().format("%Y-%m-%d").to_string(),
+        total_bytes: wasm_sizes.total_bytes,
+        sections: wasm_sizes.sections,
+    });
+    std::fs::write(WASM_SIZE_HISTORY_PATH, serde_json::to_string_pretty(&history)?)?;
+    Ok(())
+}
+
+/// Fail when the wasm file grew more than the budget.
+fn check_budget(previous: &WasmSizeRecord, total_bytes: u64, max_growth_percent: f64) -> anyhow::Result<()> {
+    let growth_percent = (total_bytes as f64 - previous.total_bytes as f64) * 100.0 / previous.total_bytes.max(1) as f64;
+    println!(
+        "  {YELLOW}Compared with version {} ({}): {growth_percent:+.2}%{RESET}",
+        previous.version,
+        human_bytes(previous.total_bytes)
+    );
+    if growth_percent > max_growth_percent {
+        anyhow::bail!(
+            "The wasm file grew {growth_percent:.2}% from {} to {} bytes, the budget is {max_growth_percent}%.\nMake the wasm smaller or raise max_growth_percent in {WASM_SIZE_CONFIG_PATH}.",
+            previous.total_bytes,
+            total_bytes
+        );
+    }
+    Ok(())
+}
+
+fn human_bytes(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.2} MiB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{bytes} B")
+    }
+}
+
+/// Parse the sections of the wasm binary format. Only the sizes are needed, so most sections are skipped.
+fn parse_wasm_sizes(wasm_bytes: &[u8]) -> anyhow::Result<WasmSizes> {
+    let mut reader = WasmReader::new(wasm_bytes);
+    if reader.read_bytes(4)? != b"\0asm" {
+        anyhow::bail!("The file is not a wasm binary.");
+    }
+    let _version = reader.read_bytes(4)?;
+
+    let mut sections = vec![];
+    let mut imported_functions_count = 0;
+    let mut code_sizes = vec![];
+    let mut function_names = std::collections::HashMap::new();
+    while !reader.is_at_end() {
+        let id = reader.read_u8()?;
+        let size = reader.read_u32()? as usize;
+        let mut section_reader = WasmReader::new(reader.read_bytes(size)?);
+        let name = match id {
+            0 => {
+                let custom_name = section_reader.read_name()?;
+                if custom_name == "name" {
+                    function_names = read_function_names(&mut section_reader)?;
+                }
+                format!("custom {custom_name}")
+            }
+            2 => {
+                imported_functions_count = count_imported_functions(&mut section_reader)?;
+                "import".to_string()
+            }
+            10 => {
+                for _ in 0..section_reader.read_u32()? {
+                    let body_size = section_reader.read_u32()?;
+                    section_reader.read_bytes(body_size as usize)?;
+                    code_sizes.push(body_size as u64);
+                }
+                "code".to_string()
+            }
+            _ => SECTION_NAMES.get(id as usize).unwrap_or(&"unknown").to_string(),
+        };
+        sections.push(SectionSize { name, bytes: size as u64 });
+    }
+
+    let mut functions: Vec<(String, u64)> = if function_names.is_empty() {
+        vec![]
+    } else {
+        code_sizes
+            .iter()
+            .enumerate()
+            .map(|(i, bytes)| {
+                let index = imported_functions_count + i as u32;
+                let name = function_names.remove(&index).unwrap_or_else(|| format!("function[{index}]"));
+                (name, *bytes)
+            })
+            .collect()
+    };
+    functions.sort_by_key(|function| std::cmp::Reverse(function.1));
+    sections.sort_by_key(|section| std::cmp::Reverse(section.bytes));
+    Ok(WasmSizes {
+        total_bytes: wasm_bytes.len() as u64,
+        sections,
+        functions,
+    })
+}
+
+/// The names of the known section ids. The custom section 0 has its own name.
+const SECTION_NAMES: [&str; 14] = [
+    "custom",
+    "type",
+    "import",
+    "function",
+    "table",
+    "memory",
+    "global",
+    "export",
+    "start",
+    "element",
+    "code",
+    "data",
+    "data count",
+    "tag",
+];
+
+/// The code section does not count the imported functions, but the function index does.
+fn count_imported_functions(reader: &mut WasmReader) -> anyhow::Result<u32> {
+    let mut imported_functions_count = 0;
+    for _ in 0..reader.read_u32()? {
+        let _module = reader.read_name()?;
+        let _field = reader.read_name()?;
+        match reader.read_u8()? {
+            // function with type index
+            0 => {
+                reader.read_u32()?;
+                imported_functions_count += 1;
+            }
+            // table with reference type and limits
+            1 => {
+                reader.read_u8()?;
+                reader.read_limits()?;
+            }
+            2 => reader.read_limits()?,
+            // global with value type and mutability
+            3 => {
+                reader.read_u8()?;
+                reader.read_u8()?;
+            }
+            // tag with attribute and type index
+            4 => {
+                reader.read_u8()?;
+                reader.read_u32()?;
+            }
+            kind => anyhow::bail!("Unknown import kind {kind} in the wasm file."),
+        }
+    }
+    Ok(imported_functions_count)
+}
+
+/// The subsection 1 of the name section has the function names.
+fn read_function_names(reader: &mut WasmReader) -> anyhow::Result<std::collections::HashMap<u32, String>> {
+    let mut function_names = std::collections::HashMap::new();
+    while !reader.is_at_end() {
+        let subsection_id = reader.read_u8()?;
+        let size = reader.read_u32()? as usize;
+        let mut subsection_reader = WasmReader::new(reader.read_bytes(size)?);
+        if subsection_id == 1 {
+            for _ in 0..subsection_reader.read_u32()? {
+                let index = subsection_reader.read_u32()?;
+                function_names.insert(index, subsection_reader.read_name()?);
+            }
+        }
+    }
+    Ok(function_names)
+}
+
+/// Reads the primitive values of the wasm binary format.
+struct WasmReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> WasmReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        WasmReader { bytes, position: 0 }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    fn read_bytes(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        let Some(bytes) = self.bytes.get(self.position..self.position + len) else {
+            anyhow::bail!("Unexpected end of the wasm file at byte {}.", self.position);
+        };
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    /// Unsigned LEB128
+    fn read_u32(&mut self) -> anyhow::Result<u32> {
+        let mut value: u64 = 0;
+        for shift in (0..35).step_by(7) {
+            let byte = self.read_u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return u32::try_from(value).map_err(|_| anyhow::anyhow!("Too large LEB128 number in the wasm file."));
+            }
+        }
+        anyhow::bail!("Too long LEB128 number in the wasm file.")
+    }
+
+    fn read_name(&mut self) -> anyhow::Result<String> {
+        let len = self.read_u32()? as usize;
+        Ok(String::from_utf8_lossy(self.read_bytes(len)?).to_string())
+    }
+
+    /// The flags tell if the maximum exists.
+    fn read_limits(&mut self) -> anyhow::Result<()> {
+        let flags = self.read_u8()?;
+        self.read_u32()?;
+        if flags & 1 == 1 {
+            self.read_u32()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A section with id, LEB128 size and content. The test sections are shorter than 128 bytes.
+    fn section(id: u8, content: &[u8]) -> Vec<u8> {
+        [&[id, content.len() as u8], content].concat()
+    }
+
+    #[test]
+    pub fn test_parse_wasm_sizes() {
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        // type section: one function type () -> ()
+        wasm.extend(section(1, &[1, 0x60, 0, 0]));
+        // import section: one imported function env.log and one memory with limits min 1 max 2
+        wasm.extend(section(
+            2,
+            &[
+                2, 3, b'e', b'n', b'v', 3, b'l', b'o', b'g', 0, 0, 3, b'e', b'n', b'v', 3, b'm', b'e', b'm', 2, 1, 1, 2,
+            ],
+        ));
+        // function section: two functions of type 0
+        wasm.extend(section(3, &[2, 0, 0]));
+        // code section: bodies of 2 and 5 bytes
+        wasm.extend(section(10, &[2, 2, 0, 0x0b, 5, 0, 1, 1, 1, 0x0b]));
+        // name section with function names for the import and the second function
+        let function_names = [2, 0, 3, b'l', b'o', b'g', 2, 4, b'b', b'i', b'g', b'!'];
+        let name_section = [&[4, b'n', b'a', b'm', b'e', 1, function_names.len() as u8][..], &function_names].concat();
+        wasm.extend(section(0, &name_section));
+
+        let wasm_sizes = parse_wasm_sizes(&wasm).unwrap();
+        assert_eq!(wasm_sizes.total_bytes, wasm.len() as u64);
+        assert_eq!(
+            wasm_sizes.sections.iter().map(|x| (x.name.as_str(), x.bytes)).collect::<Vec<_>>(),
+            vec![("import", 23), ("custom name", 19), ("code", 10), ("type", 4), ("function", 3)]
+        );
+        assert_eq!(wasm_sizes.functions, vec![("big!".to_string(), 5), ("function[1]".to_string(), 2)]);
+
+        assert!(parse_wasm_sizes(b"\0asm\x01\0\0\0\x0a\x05\x01").is_err());
+        assert!(parse_wasm_sizes(b"not wasm").is_err());
+    }
+
+    #[test]
+    pub fn test_check_budget() {
+        let previous = WasmSizeRecord {
+            version: "2025.101.100".to_string(),
+            date: "2025-01-01".to_string(),
+            total_bytes: 1000,
+            sections: vec![],
+        };
+        assert!(check_budget(&previous, 1050, 5.0).is_ok());
+        assert!(check_budget(&previous, 900, 5.0).is_ok());
+        assert!(check_budget(&previous, 1051, 5.0).unwrap_err().to_string().contains("grew 5.10%"));
+    }
+}
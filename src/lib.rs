@@ -81,7 +81,8 @@ pub use main_mod::LibraryError;
 /// To start the Wasm application, wasm_bindgen runs this functions
 pub fn wasm_bindgen_start() -> Result<(), JsValue> {
     // Initialize debugging for when/if something goes wrong.
-    console_error_panic_hook::set_once();
+    // The panic hook writes to the console and saves the crash report for the next start.
+    main_mod::set_panic_hook();
     // write the app version just for debug purposes
     dbg!("snake_bevy_wasm_pwa v{}", env!("CARGO_PKG_VERSION"));
 
@@ -10,31 +10,228 @@
 // The `main_mod.rs` uses the `anyhow` error library.
 // The `lib_mod.rs` uses the `thiserror` library.
 
-use unwrap::unwrap;
 use wasm_rs_dbg::dbg;
 
 mod lib_mod;
 use lib_mod::wsm;
+use lib_mod::HtmlSourceCode;
 pub use lib_mod::LibraryError;
 
+thread_local! {
+    /// The interval that updates the HUD, so it can be stopped on the next routing.
+    static HUD_INTERVAL_ID: std::cell::Cell<Option<i32>> = const { std::cell::Cell::new(None) };
+    /// The level filter of the #logs page for the action "Download as text".
+    static LOGS_LEVEL_FILTER: std::cell::Cell<log::LevelFilter> = const { std::cell::Cell::new(log::LevelFilter::Trace) };
+    /// The details of the last shown error for the action "Copy details".
+    static LAST_ERROR_DETAILS: std::cell::RefCell<String> = const { std::cell::RefCell::new(String::new()) };
+    /// The debug overlay is enabled with the hash parameter `?debug=1`.
+    static DEBUG_OVERLAY_ENABLED: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+    /// The single animation frame loop of the debug overlay.
+    static DEBUG_OVERLAY_LOOP: std::cell::RefCell<Option<wsm::AnimationFrameLoop>> = const { std::cell::RefCell::new(None) };
+    /// The timestamp of the last refresh of the debug overlay.
+    static DEBUG_OVERLAY_REFRESHED_MS: std::cell::Cell<f64> = const { std::cell::Cell::new(0.0) };
+    /// Frames and simulation ticks for the debug overlay.
+    static FRAME_STATS: std::cell::RefCell<lib_mod::FrameStats> = const { std::cell::RefCell::new(lib_mod::FrameStats::new()) };
+}
+
+/// The debug overlay is refreshed only every this milliseconds, so it does not slow down the frames.
+const DEBUG_OVERLAY_REFRESH_MS: f64 = 500.0;
+
 /// entry point just like for cli-bin-executable
 pub fn main() {
     // logging is essential for every project
-    wasm_logger::init(wasm_logger::Config::default());
+    // the logger writes to the console and keeps the last records for the #logs page
+    wsm::init_logger(log::LevelFilter::Debug);
     log::info!("main() started");
 
-    let args = get_args_from_hash_fragment();
-    routing_by_arguments(args);
+    show_crash_report_from_last_time();
+
+    routing_by_hash_fragment();
+}
+
+/// The value of the parameter after ? in the hash fragment like `#logs?debug=1`.  
+fn hash_query_parameter<'a>(location_hash: &'a str, name: &str) -> Option<&'a str> {
+    let (_path, query) = location_hash.split_once('?')?;
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _value)| *key == name)
+        .map(|(_key, value)| value)
+}
+
+/// Show or hide the debug overlay with FPS, frame times and memory.  
+fn enable_debug_overlay(enable: bool) -> anyhow::Result<()> {
+    if enable == DEBUG_OVERLAY_ENABLED.get() {
+        return Ok(());
+    }
+    DEBUG_OVERLAY_ENABLED.set(enable);
+    // toggling off and on before the next frame must not start a second loop
+    if let Some(animation_frame_loop) = DEBUG_OVERLAY_LOOP.take() {
+        animation_frame_loop.cancel()?;
+    }
+    if enable {
+        FRAME_STATS.with_borrow_mut(|frame_stats| *frame_stats = lib_mod::FrameStats::new());
+        DEBUG_OVERLAY_LOOP.set(Some(wsm::start_animation_frame_loop(&on_animation_frame)?));
+    } else {
+        wsm::set_html_element_inner_text("div_for_debug_overlay", "")?;
+    }
+    // return
+    Ok(())
+}
+
+/// Called before every repaint. Returns false to stop the loop.  
+fn on_animation_frame(timestamp_ms: f64) -> bool {
+    if !DEBUG_OVERLAY_ENABLED.get() {
+        return false;
+    }
+    FRAME_STATS.with_borrow_mut(|frame_stats| frame_stats.record_frame(timestamp_ms));
+    if timestamp_ms - DEBUG_OVERLAY_REFRESHED_MS.get() >= DEBUG_OVERLAY_REFRESH_MS {
+        DEBUG_OVERLAY_REFRESHED_MS.set(timestamp_ms);
+        if let Err(err) = refresh_debug_overlay(timestamp_ms) {
+            show_error(&err);
+            // the stopped loop is canceled and started again when the overlay is enabled the next time
+            DEBUG_OVERLAY_ENABLED.set(false);
+            return false;
+        }
+    }
+    // return
+    true
+}
+
+/// only the changed values are written into the DOM  
+fn refresh_debug_overlay(timestamp_ms: f64) -> anyhow::Result<()> {
+    let summary = FRAME_STATS.with_borrow(|frame_stats| frame_stats.summary(timestamp_ms));
+    wsm::update_html_source_code("div_for_debug_overlay", render_debug_overlay(&summary, wsm::wasm_memory_bytes()?)?)?;
+    // return
+    Ok(())
+}
+
+/// render the debug overlay, can return error  
+fn render_debug_overlay(summary: &lib_mod::FrameStatsSummary, memory_bytes: u32) -> anyhow::Result<HtmlSourceCode> {
+    let mut html_source_code = HtmlSourceCode::new(
+        r#"<div class="div_debug_overlay">
+    <div>FPS: <span data-key="fps">{ph_fps}</span> ticks/s: <span data-key="tps">{ph_tps}</span></div>
+    <div>p50/p95/p99/max ms: <span data-key="percentiles">{ph_percentiles}</span></div>
+    <div>frame time: <span data-key="histogram">{ph_histogram}</span></div>
+    <div>wasm memory: <span data-key="memory">{ph_memory}</span></div>
+</div>"#,
+    );
+    html_source_code.replace_text_node("{ph_fps}", &summary.fps.to_string())?;
+    html_source_code.replace_text_node("{ph_tps}", &summary.ticks_per_second.to_string())?;
+    html_source_code.replace_text_node(
+        "{ph_percentiles}",
+        &format!(
+            "{:.1}/{:.1}/{:.1}/{:.1}",
+            summary.p50_ms, summary.p95_ms, summary.p99_ms, summary.max_ms
+        ),
+    )?;
+    html_source_code.replace_text_node("{ph_histogram}", &summary.histogram_bars())?;
+    html_source_code.replace_text_node("{ph_memory}", &format!("{:.1} MiB", memory_bytes as f64 / 1024.0 / 1024.0))?;
+    // return
+    Ok(html_source_code)
+}
+
+/// Panic hook writes to the console and saves the crash report into the local storage.  
+/// With `panic = "abort"` the app just freezes, so on the next start it shows the report.  
+/// The hook must never panic itself, so all errors are ignored.  
+pub fn set_panic_hook() {
+    std::panic::set_hook(Box::new(|panic_info| {
+        console_error_panic_hook::hook(panic_info);
+        let message = if let Some(message) = panic_info.payload().downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = panic_info.payload().downcast_ref::<String>() {
+            message.to_string()
+        } else {
+            "unknown panic".to_string()
+        };
+        let crash_report = lib_mod::CrashReport {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            time: wsm::now_iso_string(),
+            route: wsm::location_hash().unwrap_or_default(),
+            location: panic_info
+                .location()
+                .map(|location| format!("{}:{}:{}", location.file(), location.line(), location.column()))
+                .unwrap_or_default(),
+            message,
+        };
+        let _ = wsm::local_storage_set(lib_mod::CRASH_REPORT_STORAGE_KEY, &crash_report.to_text());
+    }));
+}
+
+/// If the app crashed last time, show the dialog with the crash report.  
+fn show_crash_report_from_last_time() {
+    let result = wsm::local_storage_get(lib_mod::CRASH_REPORT_STORAGE_KEY)
+        .map_err(anyhow::Error::from)
+        .and_then(|text| match text.as_deref().and_then(lib_mod::CrashReport::from_text) {
+            Some(crash_report) => show_crash_report_dialog(&crash_report),
+            None => Ok(()),
+        });
+    if let Err(err) = result {
+        log::error!("{err}");
+    }
+}
+
+/// inject the modal dialog with the crash report, can return error  
+fn show_crash_report_dialog(crash_report: &lib_mod::CrashReport) -> anyhow::Result<()> {
+    wsm::inject_html_source_code_with_handlers(
+        "div_for_modal",
+        render_crash_report_dialog(crash_report)?,
+        &[
+            ("export_crash_report", &on_click_export_crash_report),
+            ("close_crash_report", &on_click_close_crash_report),
+        ],
+    )?;
+    // return
+    Ok(())
+}
+
+/// render the modal dialog with the crash report, can return error  
+fn render_crash_report_dialog(crash_report: &lib_mod::CrashReport) -> anyhow::Result<HtmlSourceCode> {
+    let mut html_source_code = HtmlSourceCode::new(
+        r#"<div class="w3_modal">
+    <div class="w3_modal_content">
+        <p class="bold">The game crashed last time</p>
+        <p>Please export the details and attach them to a bug report.</p>
+        <pre class="small">{ph_details}</pre>
+        <input type="button" class="button" value="Export details" data-on-click="export_crash_report"/>
+        <input type="button" class="button" value="Close" data-on-click="close_crash_report"/>
+    </div>
+</div>"#,
+    );
+    html_source_code.replace_text_node("{ph_details}", &crash_report.to_text())?;
+    // return
+    Ok(html_source_code)
+}
+
+/// the declared handler "export_crash_report" calls this function  
+fn on_click_export_crash_report() {
+    let result = wsm::local_storage_get(lib_mod::CRASH_REPORT_STORAGE_KEY)
+        .and_then(|text| wsm::download_text_file("snake_bevy_wasm_pwa_crash_report.txt", &text.unwrap_or_default()));
+    if let Err(err) = result {
+        show_error(&err.into());
+    }
 }
 
-/// get args from hash fragment
-fn get_args_from_hash_fragment() -> Vec<String> {
+/// the declared handler "close_crash_report" calls this function  
+/// The report is removed, so it is shown only once.  
+fn on_click_close_crash_report() {
+    let result =
+        wsm::local_storage_remove(lib_mod::CRASH_REPORT_STORAGE_KEY).and_then(|()| wsm::set_html_element_inner_text("div_for_modal", ""));
+    if let Err(err) = result {
+        show_error(&err.into());
+    }
+}
+
+/// get args from hash fragment, can return error
+fn get_args_from_hash_fragment() -> anyhow::Result<Vec<String>> {
     // region: In browser we can use 'local routing' on url path with # fragment
     // but sometimes it does not reload the page, because the browser thinks # is an anchor on the same page
     // So we need to add a listener also to this other event.
     // http://localhost:4000/snake_bevy_wasm_pwa/#arg_1/arg_2
-    let location = wsm::window().location();
-    let mut location_hash_fragment = unwrap!(location.hash());
+    let location_hash_fragment = wsm::location_hash()?;
+    // the optional parameters after ? are not arguments
+    let (location_hash_fragment, _hash_query) = location_hash_fragment.split_once('?').unwrap_or((&location_hash_fragment, ""));
+    let mut location_hash_fragment = location_hash_fragment.to_string();
     // the hash is not decoded automatically !
     // dbg! is now writing to the console, crate wasm-rs-dbg
     dbg!(&location_hash_fragment);
@@ -50,7 +247,95 @@ fn get_args_from_hash_fragment() -> Vec<String> {
     let args = location_hash_fragment.split("/");
     let args: Vec<String> = args.map(|x| x.to_string()).collect();
     dbg!(&args);
-    args
+    Ok(args)
+}
+
+/// Show the error panel in div_for_errors. All errors are presented here.  
+/// Here is the last place I can deal with the error.  
+/// If the panel cannot be rendered, fallback to plain text and then only to the log.  
+fn show_error(err: &anyhow::Error) {
+    log::error!("{err}");
+    let code = error_code(err);
+    let route = wsm::location_hash().unwrap_or_default();
+    LAST_ERROR_DETAILS.set(format!(
+        "{code} {title}\n{err}\nversion: {version}\nroute: {route}",
+        title = lib_mod::title_from_code(code),
+        version = env!("CARGO_PKG_VERSION")
+    ));
+    if let Err(err_in_panel) = show_error_panel(code, &err.to_string()) {
+        log::error!("{err_in_panel}");
+        if let Err(err_in_div) = wsm::set_html_element_inner_text("div_for_errors", &format!("Error {code}: {err}")) {
+            log::error!("{err_in_div}");
+        }
+    }
+}
+
+/// The stable error code from LibraryError or WebSysError inside anyhow.  
+fn error_code(err: &anyhow::Error) -> &'static str {
+    if let Some(library_error) = err.downcast_ref::<LibraryError>() {
+        library_error.code()
+    } else if let Some(web_sys_error) = err.downcast_ref::<wsm::WebSysError>() {
+        web_sys_error.code()
+    } else {
+        lib_mod::UNKNOWN_ERROR_CODE
+    }
+}
+
+/// inject the error panel and wire its actions, can return error  
+fn show_error_panel(code: &str, message: &str) -> anyhow::Result<()> {
+    wsm::inject_html_source_code_with_handlers(
+        "div_for_errors",
+        render_error_panel(code, message)?,
+        &[("copy_error_details", &on_click_copy_error_details)],
+    )?;
+    // return
+    Ok(())
+}
+
+/// render the error panel with title, message and recovery actions  
+fn render_error_panel(code: &str, message: &str) -> anyhow::Result<HtmlSourceCode> {
+    let mut html_source_code = HtmlSourceCode::new(
+        r#"<div class="div_error_panel" role="alert">
+    <p class="bold">{ph_title}</p>
+    <p>{ph_message}</p>
+    <p class="small">Error code: {ph_code}</p>
+    <a class="button" href="/snake_bevy_wasm_pwa/#page_with_inputs">Back to menu</a>
+    <input type="button" class="button" value="Copy details" data-on-click="copy_error_details"/>
+</div>"#,
+    );
+    html_source_code.replace_text_node("{ph_title}", lib_mod::title_from_code(code))?;
+    html_source_code.replace_text_node("{ph_message}", message)?;
+    html_source_code.replace_text_node("{ph_code}", code)?;
+    // return
+    Ok(html_source_code)
+}
+
+/// Errors are cleared on every navigation, so they don't stick around.  
+fn clear_error() -> anyhow::Result<()> {
+    wsm::set_html_element_inner_text("div_for_errors", "")?;
+    LAST_ERROR_DETAILS.take();
+    // return
+    Ok(())
+}
+
+/// the declared handler "copy_error_details" calls this function  
+fn on_click_copy_error_details() {
+    let details = LAST_ERROR_DETAILS.with_borrow(|details| details.clone());
+    if let Err(err) = wsm::copy_text_to_clipboard(&details) {
+        log::error!("{err}");
+    }
+}
+
+/// read the hash fragment and route, errors are shown in div_for_errors  
+fn routing_by_hash_fragment() {
+    let debug_overlay = wsm::location_hash().is_ok_and(|location_hash| hash_query_parameter(&location_hash, "debug") == Some("1"));
+    if let Err(err) = enable_debug_overlay(debug_overlay) {
+        show_error(&err);
+    }
+    match get_args_from_hash_fragment() {
+        Ok(args) => routing_by_arguments(args),
+        Err(err) => show_error(&err),
+    }
 }
 
 /// routing by arguments  
@@ -59,9 +344,18 @@ fn get_args_from_hash_fragment() -> Vec<String> {
 /// 2. or from event change_hash  
 /// 3. or can be called from a wasm function directly  
 fn routing_by_arguments(args: Vec<String>) {
+    if let Err(err) = try_routing_by_arguments(args) {
+        show_error(&err);
+    }
+}
+
+/// routing by arguments, can return error  
+fn try_routing_by_arguments(args: Vec<String>) -> anyhow::Result<()> {
     // every page must have the header and onhashchange
-    wsm::add_listener_for_onhashchange(&on_hash_change);
-    header();
+    wsm::add_listener_for_onhashchange(&on_hash_change)?;
+    stop_hud_timer()?;
+    clear_error()?;
+    header()?;
     // endregion
 
     // transforming Vec<String> to Vec<&str>, because we need that in the match expression
@@ -69,64 +363,63 @@ fn routing_by_arguments(args: Vec<String>) {
 
     // super simple argument parsing.
     match args.get(1).copied() {
-        None => page_with_inputs(),
-        Some("page_with_inputs") => page_with_inputs(),
-        Some("help") => print_help(),
+        None | Some("page_with_inputs") => page_with_inputs()?,
+        Some("help") => print_help()?,
+        Some("logs") => page_logs(args.get(2).copied())?,
         Some("print") => {
             match args.get(2).copied() {
                 // second argument
-                Some(greet_name) => print_greet_name(greet_name),
-                None => wsm::set_html_element_inner_text("div_for_errors", "Error: Missing second argument for print."),
+                Some(greet_name) => print_greet_name(greet_name)?,
+                None => return Err(LibraryError::MissingArgument("the name for print".to_string()).into()),
             }
         }
         Some("upper") => {
             match args.get(2).copied() {
                 // second argument
-                Some(greet_name) => {
-                    // this can return an error. Here is the last place I can deal with the error.
-                    match upper_greet_name(greet_name) {
-                        // do nothing
-                        Ok(()) => (),
-                        // log error from anyhow
-                        Err(err) => wsm::set_html_element_inner_text("div_for_errors", &format!("Error: {err}")),
-                    }
-                }
-                None => wsm::set_html_element_inner_text("div_for_errors", "Error: Missing second argument for upper."),
+                // this can return an error. Use the ? syntax to bubble the error up.
+                Some(greet_name) => upper_greet_name(greet_name)?,
+                None => return Err(LibraryError::MissingArgument("the name for upper".to_string()).into()),
             }
         }
-        _ => wsm::set_html_element_inner_text(
-            "div_for_errors",
-            "Error: Unrecognized arguments. Try \n http://localhost:4000/snake_bevy_wasm_pwa/#help",
-        ),
+        _ => return Err(LibraryError::UnrecognizedArguments(args[1..].join("/")).into()),
     }
+    // return
+    Ok(())
 }
 
 /// the listener calls this function  
 fn on_hash_change() {
     dbg!("on_hash_change");
-    let args = get_args_from_hash_fragment();
-    routing_by_arguments(args);
+    routing_by_hash_fragment();
+}
+
+/// render header with Home and Help, can return error  
+fn header() -> anyhow::Result<()> {
+    wsm::inject_html_source_code("div_for_wasm_html_injecting", render_header())?;
+    // return
+    Ok(())
 }
 
-/// render header with Home and Help  
-fn header() {
-    let html_source_code = wsm::HtmlSourceCode::new(
+/// render the header html without touching the DOM  
+fn render_header() -> HtmlSourceCode {
+    HtmlSourceCode::new(
         r#"
 <div class="div_header">
     <a href="/snake_bevy_wasm_pwa/#page_with_inputs"><span class="fa-solid fa-home"></span>Home</a>
     &nbsp;
     <a href="/snake_bevy_wasm_pwa/#help"><span class="fa-solid fa-question-circle"></span>Help</a>
     &nbsp;
+    <a href="/snake_bevy_wasm_pwa/#logs"><span class="fa-solid fa-clipboard-list"></span>Logs</a>
+    &nbsp;
 </div>
 <div>&nbsp;</div>
 <div id="div_body"></div>
 "#,
-    );
-    html_source_code.inject_into_dom_element("div_for_wasm_html_injecting");
+    )
 }
 
-/// print help  
-fn print_help() {
+/// print help, can return error  
+fn print_help() -> anyhow::Result<()> {
     wsm::set_html_element_inner_text(
         "div_body",
         r#"Welcome to snake_bevy_wasm_pwa !
@@ -138,21 +431,38 @@ http://localhost:4000/snake_bevy_wasm_pwa/
 http://localhost:4000/snake_bevy_wasm_pwa/#help
 http://localhost:4000/snake_bevy_wasm_pwa/#print/world
 http://localhost:4000/snake_bevy_wasm_pwa/#upper/world
+http://localhost:4000/snake_bevy_wasm_pwa/#logs
+http://localhost:4000/snake_bevy_wasm_pwa/#logs/warn
 
 This command should return an error:
 http://localhost:4000/snake_bevy_wasm_pwa/#upper/WORLD
 
 © 2025 bestia.dev  MIT License github.com/automation-tasks-rs/cargo-auto
 "#,
-    );
+    )?;
+    // return
+    Ok(())
+}
+
+/// render first page, can return error  
+fn page_with_inputs() -> anyhow::Result<()> {
+    let html_source_code = render_page_with_inputs()?;
+    dbg!(html_source_code.finalize()?);
+    // the template declares the handlers with data-on-click
+    wsm::inject_html_source_code_with_handlers("div_body", html_source_code, &[("run", &on_click_btn_run)])?;
+    wsm::inject_html_source_code("div_hud", render_hud(0, &wsm::now_time_as_string())?)?;
+    start_hud_timer()?;
+    // return
+    Ok(())
 }
 
-/// render first page  
-fn page_with_inputs() {
+/// render the first page html without touching the DOM, can return error  
+fn render_page_with_inputs() -> anyhow::Result<HtmlSourceCode> {
     // rust has `Raw string literals` that are great!
     // just add r# before the starting double quotes and # after the ending double quotes.
-    let mut html_source_code = wsm::HtmlSourceCode::new(
+    let mut html_source_code = HtmlSourceCode::new(
         r#"<h1>snake_bevy_wasm_pwa</h1>
+<div id="div_hud"></div>
 <p>Write a command in the Argument 1: print or upper</p>
 <div class="input-wrap">
     <label for="arg_1">Argument 1:</label>  
@@ -165,42 +475,187 @@ fn page_with_inputs() {
 </div>
 <p>Click on Run</p>
 <div class="input-wrap">
-    <input type="button" class="button" id="btn_run" value="Run"/>
+    <input type="button" class="button" id="btn_run" value="Run" data-on-click="run"/>
 </div>
+<p>Examples:</p>
+<ul>
+{ph_examples}
+</ul>
 {ph_elem_p_1}
         "#,
     );
 
     // {ph_...} is the prefix for placeholder to make the string unique and distinctive
-    html_source_code.replace_attribute_value("{ph_arg_1}", "upper");
-    html_source_code.replace_attribute_value("{ph_arg_2}", "world");
+    // a misspelled placeholder returns an error
+    html_source_code.replace_attribute_value("{ph_arg_1}", "upper")?;
+    html_source_code.replace_attribute_value("{ph_arg_2}", "world")?;
 
-    let mut fragment = wsm::HtmlSourceCode::new(r#"<p class="{ph_attr_class_1}">{ph_text_node_1}</p>"#);
-    fragment.replace_attribute_value("{ph_attr_class_1}", "small");
-    fragment.replace_text_node("{ph_text_node_1}", "bestia.dev");
-    html_source_code.replace_html_source_code("{ph_elem_p_1}", &fragment);
+    // the fragment template is rendered once for every item
+    let examples = ["print/world", "upper/world", "upper/WORLD"];
+    html_source_code.replace_repeated_fragment(
+        "{ph_examples}",
+        r#"<li><a href="{ph_href}">{ph_text}</a></li>"#,
+        examples,
+        |fragment, example| {
+            fragment.replace_url_attribute_value("{ph_href}", &format!("/snake_bevy_wasm_pwa/#{example}"))?;
+            fragment.replace_text_node("{ph_text}", example)
+        },
+    )?;
 
-    dbg!(html_source_code.get_html());
-    html_source_code.inject_into_dom_element("div_body");
-    wsm::add_listener_to_button("btn_run", &on_click_btn_run);
+    let mut fragment = HtmlSourceCode::new(r#"<p class="{ph_attr_class_1}">{ph_text_node_1}</p>"#);
+    fragment.replace_attribute_value("{ph_attr_class_1}", "small")?;
+    fragment.replace_text_node("{ph_text_node_1}", "bestia.dev")?;
+    html_source_code.replace_html_source_code("{ph_elem_p_1}", &fragment)?;
+    // return
+    Ok(html_source_code)
 }
 
-/// the listener calls this function  
+/// The in-app log console, because testers on phones cannot open the devtools console.  
+/// The optional argument is the level filter: error, warn, info, debug or trace.  
+fn page_logs(level_filter_arg: Option<&str>) -> anyhow::Result<()> {
+    let level_filter = match level_filter_arg {
+        None => log::LevelFilter::Trace,
+        Some(arg) => arg
+            .parse::<log::LevelFilter>()
+            .map_err(|_| LibraryError::UnrecognizedArguments(format!("logs/{arg}")))?,
+    };
+    LOGS_LEVEL_FILTER.set(level_filter);
+    let html_source_code = wsm::with_log_ring_buffer(|log_ring_buffer| render_logs(log_ring_buffer, level_filter))?;
+    wsm::inject_html_source_code_with_handlers("div_body", html_source_code, &[("download_logs", &on_click_download_logs)])?;
+    // return
+    Ok(())
+}
+
+/// render the log records with the newest first, can return error  
+fn render_logs(log_ring_buffer: &lib_mod::LogRingBuffer, level_filter: log::LevelFilter) -> anyhow::Result<HtmlSourceCode> {
+    let mut html_source_code = HtmlSourceCode::new(
+        r#"<h2>Logs</h2>
+<p>Filter: {ph_filters}</p>
+<div class="input-wrap">
+    <input type="button" class="button" value="Download as text" data-on-click="download_logs"/>
+</div>
+<table class="small">
+{ph_rows}
+</table>
+"#,
+    );
+    let level_filters = [
+        log::LevelFilter::Trace,
+        log::LevelFilter::Debug,
+        log::LevelFilter::Info,
+        log::LevelFilter::Warn,
+        log::LevelFilter::Error,
+    ];
+    html_source_code.replace_repeated_fragment(
+        "{ph_filters}",
+        r#"<a class="{ph_class}" href="{ph_href}">{ph_text}</a> "#,
+        level_filters,
+        |fragment, filter| {
+            fragment.replace_attribute_value("{ph_class}", if filter == level_filter { "bold" } else { "" })?;
+            fragment.replace_url_attribute_value(
+                "{ph_href}",
+                &format!("/snake_bevy_wasm_pwa/#logs/{}", filter.as_str().to_lowercase()),
+            )?;
+            fragment.replace_text_node("{ph_text}", filter.as_str())
+        },
+    )?;
+    let mut log_entries: Vec<&lib_mod::LogEntry> = log_ring_buffer.filtered(level_filter).collect();
+    log_entries.reverse();
+    html_source_code.replace_repeated_fragment(
+        "{ph_rows}",
+        r#"<tr><td>{ph_time}</td><td>{ph_level}</td><td>{ph_target}</td><td>{ph_message}</td></tr>"#,
+        log_entries,
+        |fragment, log_entry| {
+            fragment.replace_text_node("{ph_time}", &log_entry.time)?;
+            fragment.replace_text_node("{ph_level}", log_entry.level.as_str())?;
+            fragment.replace_text_node("{ph_target}", &log_entry.target)?;
+            fragment.replace_text_node("{ph_message}", &log_entry.message)
+        },
+    )?;
+    // return
+    Ok(html_source_code)
+}
+
+/// the declared handler "download_logs" calls this function  
+fn on_click_download_logs() {
+    let text = wsm::with_log_ring_buffer(|log_ring_buffer| log_ring_buffer.to_text(LOGS_LEVEL_FILTER.get()));
+    if let Err(err) = wsm::download_text_file("snake_bevy_wasm_pwa_logs.txt", &text) {
+        show_error(&err.into());
+    }
+}
+
+/// render the HUD with score and time, can return error  
+/// The values are inside elements with data-key, so the HUD can be updated incrementally.  
+fn render_hud(score: u32, time: &str) -> anyhow::Result<HtmlSourceCode> {
+    let mut html_source_code = HtmlSourceCode::new(
+        r#"<p class="small">Score: <span data-key="score">{ph_score}</span> Time: <span data-key="time">{ph_time}</span></p>"#,
+    );
+    html_source_code.replace_text_node("{ph_score}", &score.to_string())?;
+    html_source_code.replace_text_node("{ph_time}", time)?;
+    // return
+    Ok(html_source_code)
+}
+
+/// start the interval that updates the HUD every second, can return error  
+fn start_hud_timer() -> anyhow::Result<()> {
+    let interval_id = wsm::set_interval(1000, &on_hud_timer)?;
+    HUD_INTERVAL_ID.set(Some(interval_id));
+    // return
+    Ok(())
+}
+
+/// stop the interval that updates the HUD, can return error  
+fn stop_hud_timer() -> anyhow::Result<()> {
+    if let Some(interval_id) = HUD_INTERVAL_ID.take() {
+        wsm::clear_interval(interval_id)?;
+    }
+    // return
+    Ok(())
+}
+
+/// the interval calls this function  
+fn on_hud_timer() {
+    if let Err(err) = try_on_hud_timer() {
+        show_error(&err);
+    }
+    log::trace!("DOM writes: {}", wsm::dom_write_count());
+}
+
+/// update the HUD, can return error  
+fn try_on_hud_timer() -> anyhow::Result<()> {
+    // the HUD timer is the simulation tick for now
+    let now_ms = wsm::performance_now()?;
+    FRAME_STATS.with_borrow_mut(|frame_stats| frame_stats.record_tick(now_ms));
+    // only the changed time is written into the DOM, not the whole HUD
+    wsm::update_html_source_code("div_hud", render_hud(0, &wsm::now_time_as_string())?)?;
+    // return
+    Ok(())
+}
+
+/// the declared handler "run" calls this function  
 fn on_click_btn_run() {
-    let arg_1 = wsm::get_input_element_value_string_by_id("arg_1");
-    let arg_2 = wsm::get_input_element_value_string_by_id("arg_2");
-    if !arg_1.is_empty() && !arg_2.is_empty() {
-        // pass arguments as URL in a new tab
-        let url = format!("/snake_bevy_wasm_pwa/#{arg_1}/{arg_2}");
-        wsm::open_url(&url);
-    } else {
+    if let Err(err) = try_on_click_btn_run() {
         // write on the same web page
-        wsm::set_html_element_inner_text("div_for_errors", "Error: Both arguments are mandatory.");
+        show_error(&err);
     }
 }
 
-/// print my name  
-fn print_greet_name(greet_name: &str) {
+/// read the arguments and open the url, can return error  
+fn try_on_click_btn_run() -> anyhow::Result<()> {
+    let arg_1 = wsm::get_input_element_value_string_by_id("arg_1")?;
+    let arg_2 = wsm::get_input_element_value_string_by_id("arg_2")?;
+    if arg_1.is_empty() || arg_2.is_empty() {
+        return Err(LibraryError::MissingArgument("Argument 1 and Argument 2 are both mandatory".to_string()).into());
+    }
+    // pass arguments as URL in a new tab
+    let url = format!("/snake_bevy_wasm_pwa/#{arg_1}/{arg_2}");
+    wsm::open_url(&url)?;
+    // return
+    Ok(())
+}
+
+/// print my name, can return error  
+fn print_greet_name(greet_name: &str) -> anyhow::Result<()> {
     wsm::set_html_element_inner_text(
         "div_body",
         &format!(
@@ -209,7 +664,9 @@ fn print_greet_name(greet_name: &str) {
 "#,
             lib_mod::format_hello_phrase(greet_name)
         ),
-    );
+    )?;
+    // return
+    Ok(())
 }
 
 /// print my name upper, can return error  
@@ -224,7 +681,141 @@ fn upper_greet_name(greet_name: &str) -> anyhow::Result<()> {
 {upper}
 "#
         ),
-    );
+    )?;
     // return
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Compare the rendered html with the snapshot file in `src/main_mod/snapshots/`.  
+    /// Run the tests with `UPDATE_SNAPSHOTS=1 cargo test` to write the new snapshot after an intended change.  
+    fn assert_snapshot(snapshot_name: &str, html_source_code: &HtmlSourceCode) {
+        let html = html_source_code.finalize().expect("leftover placeholder");
+        let snapshot_path = format!("{}/src/main_mod/snapshots/{snapshot_name}.html", env!("CARGO_MANIFEST_DIR"));
+        if std::env::var("UPDATE_SNAPSHOTS").is_ok() {
+            std::fs::write(&snapshot_path, &html).expect("cannot write snapshot");
+        }
+        let snapshot = std::fs::read_to_string(&snapshot_path).expect("cannot read snapshot");
+        assert_eq!(html, snapshot, "rendered html differs from snapshot {snapshot_path}");
+    }
+
+    #[test]
+    pub fn test_render_header() {
+        assert_snapshot("header", &render_header());
+    }
+
+    #[test]
+    pub fn test_render_error_panel() {
+        let err = anyhow::Error::from(LibraryError::Uppercase("<WORLD>".to_string()));
+        assert_eq!(error_code(&err), "E100");
+        assert_snapshot(
+            "error_panel",
+            &render_error_panel(error_code(&err), &err.to_string()).expect("error"),
+        );
+        assert_eq!(error_code(&anyhow::anyhow!("other")), lib_mod::UNKNOWN_ERROR_CODE);
+    }
+
+    #[test]
+    pub fn test_render_logs() {
+        let mut log_ring_buffer = lib_mod::LogRingBuffer::new(10);
+        for (level, message) in [(log::Level::Debug, "main() started"), (log::Level::Error, "<broken>")] {
+            log_ring_buffer.push(lib_mod::LogEntry {
+                time: "10:00:00".to_string(),
+                level,
+                target: "snake_bevy_wasm_pwa".to_string(),
+                message: message.to_string(),
+            });
+        }
+        assert_snapshot("logs", &render_logs(&log_ring_buffer, log::LevelFilter::Trace).expect("error"));
+        assert_snapshot(
+            "logs_error",
+            &render_logs(&log_ring_buffer, log::LevelFilter::Error).expect("error"),
+        );
+    }
+
+    #[test]
+    pub fn test_render_logs_with_placeholder_in_message() {
+        // show_error() logs messages like this, the logs page must still render after them
+        let mut log_ring_buffer = lib_mod::LogRingBuffer::new(10);
+        log_ring_buffer.push(lib_mod::LogEntry {
+            time: "10:00:00".to_string(),
+            level: log::Level::Error,
+            target: "snake_bevy_wasm_pwa".to_string(),
+            message: "Placeholder `{ph_x}` not found".to_string(),
+        });
+        let html = render_logs(&log_ring_buffer, log::LevelFilter::Trace)
+            .expect("error")
+            .finalize()
+            .expect("leftover placeholder");
+        assert!(html.contains("<td>Placeholder `&#123;ph_x}` not found</td>"));
+    }
+
+    #[test]
+    pub fn test_render_crash_report_dialog() {
+        let crash_report = lib_mod::CrashReport {
+            version: "0.0.12".to_string(),
+            time: "2025-08-13T10:00:00.000Z".to_string(),
+            route: "#upper/<world>".to_string(),
+            location: "src/main_mod.rs:10:5".to_string(),
+            message: "index out of bounds".to_string(),
+        };
+        assert_snapshot("crash_report_dialog", &render_crash_report_dialog(&crash_report).expect("error"));
+    }
+
+    #[test]
+    pub fn test_render_crash_report_dialog_with_placeholder_in_message() {
+        let crash_report = lib_mod::CrashReport {
+            version: "0.0.12".to_string(),
+            time: "2025-08-13T10:00:00.000Z".to_string(),
+            route: "#{ph_route}".to_string(),
+            location: "src/{ph_file}.rs:10:5".to_string(),
+            message: "Placeholder `{ph_details}` not found".to_string(),
+        };
+        let html = render_crash_report_dialog(&crash_report)
+            .expect("error")
+            .finalize()
+            .expect("leftover placeholder");
+        assert!(html.contains("Placeholder `&#123;ph_details}` not found"));
+        assert!(!html.contains("{ph_"));
+    }
+
+    #[test]
+    pub fn test_hash_query_parameter() {
+        assert_eq!(hash_query_parameter("#logs?debug=1", "debug"), Some("1"));
+        assert_eq!(hash_query_parameter("#?x=2&debug=0", "debug"), Some("0"));
+        assert_eq!(hash_query_parameter("#logs", "debug"), None);
+    }
+
+    #[test]
+    pub fn test_render_debug_overlay() {
+        let mut frame_stats = lib_mod::FrameStats::new();
+        for frame in 0..=30 {
+            frame_stats.record_frame(frame as f64 * 16.0);
+        }
+        let summary = frame_stats.summary(480.0);
+        assert_snapshot("debug_overlay", &render_debug_overlay(&summary, 1_179_648).expect("error"));
+    }
+
+    #[test]
+    pub fn test_render_hud() {
+        let mounted = render_hud(7, "10:00:00").expect("error");
+        assert_snapshot("hud", &mounted);
+        // every tick only the time changes: one DOM write instead of the whole HUD
+        let patches = render_hud(7, "10:00:01").expect("error").keyed_patches(&mounted).expect("error");
+        assert_eq!(
+            patches,
+            vec![lib_mod::KeyedPatch::Text {
+                key: "time".to_string(),
+                text: "10:00:01".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    pub fn test_render_page_with_inputs() {
+        assert_snapshot("page_with_inputs", &render_page_with_inputs().expect("error"));
+    }
+}
@@ -14,12 +14,21 @@
 // The `main_mod.rs` uses the `anyhow` error library.
 // The `lib_mod.rs` uses the `thiserror` library.
 
+mod crash_report_mod;
+mod frame_stats_mod;
 mod hello_mod;
+mod html_source_code_mod;
+mod log_buffer_mod;
 pub mod web_sys_mod;
 
 // re-exports
+pub use crash_report_mod::{CrashReport, CRASH_REPORT_STORAGE_KEY};
+pub use frame_stats_mod::{FrameStats, FrameStatsSummary};
 pub use hello_mod::format_hello_phrase;
 pub use hello_mod::format_upper_hello_phrase;
+pub use html_source_code_mod::HtmlSourceCode;
+pub use html_source_code_mod::KeyedPatch;
+pub use log_buffer_mod::{LogEntry, LogRingBuffer, LOG_RING_BUFFER_CAPACITY};
 pub use web_sys_mod as wsm;
 
 /// all possible library errors for `thiserror`
@@ -27,10 +36,75 @@ pub use web_sys_mod as wsm;
 pub enum LibraryError {
     #[error("Name `{0}` is already uppercase.")]
     Uppercase(String),
+    #[error("Placeholder `{0}` not found in html source code.")]
+    PlaceholderNotFound(String),
+    #[error("Leftover placeholder `{0}` in html source code.")]
+    LeftoverPlaceholder(String),
+    #[error("Url `{0}` is not allowed. Only http(s), relative and # fragment urls are allowed.")]
+    UnsafeUrl(String),
+    #[error("Placeholder `{0}` is not inside an element with data-key.")]
+    NotKeyed(String),
+    #[error("Placeholder `{0}` is used more than once in the template, so it cannot be updated by its data-key.")]
+    DuplicateKeyedPlaceholder(String),
+    #[error("The html source code is not rendered from the same template.")]
+    TemplateMismatch,
+    #[error("Event handler `{0}` is declared in the html source code, but it is not registered.")]
+    MissingEventHandler(String),
+    #[error("Event handler `{0}` is registered, but it is not declared in the html source code.")]
+    UnusedEventHandler(String),
+    #[error("Event handler name `{0}` is not allowed. Use only a-z, 0-9 and _.")]
+    InvalidEventHandlerName(String),
+    #[error(transparent)]
+    WebSys(#[from] web_sys_mod::WebSysError),
+    #[error("Missing argument: {0}.")]
+    MissingArgument(String),
+    #[error("Unrecognized arguments `{0}`. Try the Help page.")]
+    UnrecognizedArguments(String),
     #[error("Unknown error.")]
     Unknown,
 }
 
+/// The error code of an error that is not a LibraryError.
+pub const UNKNOWN_ERROR_CODE: &str = "E999";
+
+impl LibraryError {
+    /// Stable error code to recognize the error in bug reports.  
+    /// Never change or reuse a code, just add new ones.  
+    pub fn code(&self) -> &'static str {
+        match self {
+            LibraryError::Uppercase(_) => "E100",
+            LibraryError::MissingArgument(_) => "E101",
+            LibraryError::UnrecognizedArguments(_) => "E102",
+            LibraryError::PlaceholderNotFound(_) => "E200",
+            LibraryError::LeftoverPlaceholder(_) => "E201",
+            LibraryError::UnsafeUrl(_) => "E202",
+            LibraryError::NotKeyed(_) => "E203",
+            LibraryError::TemplateMismatch => "E204",
+            LibraryError::MissingEventHandler(_) => "E205",
+            LibraryError::UnusedEventHandler(_) => "E206",
+            LibraryError::InvalidEventHandlerName(_) => "E207",
+            LibraryError::DuplicateKeyedPlaceholder(_) => "E208",
+            LibraryError::WebSys(err) => err.code(),
+            LibraryError::Unknown => UNKNOWN_ERROR_CODE,
+        }
+    }
+
+    /// Short title for the error panel, it depends only on the group of the error code.  
+    pub fn title(&self) -> &'static str {
+        title_from_code(self.code())
+    }
+}
+
+/// Short title for the error panel from the group of the error code.  
+pub fn title_from_code(code: &str) -> &'static str {
+    match code.get(..2) {
+        Some("E1") => "Invalid input",
+        Some("E2") => "The page cannot be rendered",
+        Some("E3") => "Browser error",
+        _ => "Unexpected error",
+    }
+}
+
 // ANSI colors for Linux terminal
 // https://github.com/shiena/ansicolor/blob/master/README.md
 #[allow(dead_code)]
@@ -41,3 +115,20 @@ pub const YELLOW: &str = "\x1b[33m";
 pub const GREEN: &str = "\x1b[32m";
 #[allow(dead_code)]
 pub const RESET: &str = "\x1b[0m";
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_error_code_and_title() {
+        assert_eq!(LibraryError::Uppercase("ABC".to_string()).code(), "E100");
+        assert_eq!(LibraryError::Uppercase("ABC".to_string()).title(), "Invalid input");
+        assert_eq!(LibraryError::LeftoverPlaceholder("{ph_x}".to_string()).code(), "E201");
+        assert_eq!(
+            LibraryError::WebSys(wsm::WebSysError::ElementNotFound("div_body".to_string())).code(),
+            "E302"
+        );
+        assert_eq!(LibraryError::Unknown.title(), "Unexpected error");
+    }
+}
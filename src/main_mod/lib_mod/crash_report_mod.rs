@@ -0,0 +1,60 @@
+// src/crash_report_mod.rs
+
+//! The crash report is saved by the panic hook and shown on the next start of the app.
+//! With `panic = "abort"` the PWA just freezes, so this is the only way for the user to report the crash.
+//! This module is UI agnostic and can be tested natively.
+
+/// The key in the local storage.
+pub const CRASH_REPORT_STORAGE_KEY: &str = "snake_bevy_wasm_pwa_crash_report";
+
+/// Details of a panic for the bug report.  
+#[derive(Debug, PartialEq)]
+pub struct CrashReport {
+    pub version: String,
+    pub time: String,
+    pub route: String,
+    pub location: String,
+    pub message: String,
+}
+
+impl CrashReport {
+    /// Format as text for the storage and for the export.  
+    /// The message can have more lines, so it is the last.  
+    pub fn to_text(&self) -> String {
+        format!(
+            "version: {}\ntime: {}\nroute: {}\nlocation: {}\nmessage: {}",
+            self.version, self.time, self.route, self.location, self.message
+        )
+    }
+
+    /// Parse the text from the storage. Returns None if the text is not a crash report.  
+    pub fn from_text(text: &str) -> Option<CrashReport> {
+        let mut lines = text.splitn(5, '\n');
+        let mut next_value = |key: &str| lines.next().and_then(|line| line.strip_prefix(key)).map(|value| value.to_string());
+        Some(CrashReport {
+            version: next_value("version: ")?,
+            time: next_value("time: ")?,
+            route: next_value("route: ")?,
+            location: next_value("location: ")?,
+            message: next_value("message: ")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_crash_report_round_trip() {
+        let crash_report = CrashReport {
+            version: "0.0.12".to_string(),
+            time: "2025-08-13T10:00:00.000Z".to_string(),
+            route: "#upper/world".to_string(),
+            location: "src/main_mod.rs:10:5".to_string(),
+            message: "index out of bounds\nsecond line".to_string(),
+        };
+        assert_eq!(CrashReport::from_text(&crash_report.to_text()), Some(crash_report));
+        assert_eq!(CrashReport::from_text("something else"), None);
+    }
+}
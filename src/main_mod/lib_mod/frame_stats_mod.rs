@@ -0,0 +1,176 @@
+// src/frame_stats_mod.rs
+
+//! Statistics for the debug performance overlay: FPS, simulation ticks per second and frame-time percentiles.
+//! The browser gives only timestamps in milliseconds, all the rest is calculated here.
+//! This module is UI agnostic and can be tested natively.
+
+use std::collections::VecDeque;
+
+/// How many last frame times are used for percentiles and the histogram.
+pub const FRAME_TIMES_CAPACITY: usize = 240;
+
+/// Upper edges of the histogram buckets in milliseconds. The last bucket is for everything slower.
+pub const FRAME_TIME_BUCKET_EDGES_MS: [f64; 6] = [8.0, 17.0, 25.0, 34.0, 50.0, 100.0];
+
+/// FPS and ticks per second are counted in this time window.
+const RATE_WINDOW_MS: f64 = 1000.0;
+
+/// Characters for the histogram from the lowest to the highest bar.
+const HISTOGRAM_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Collects timestamps of frames and simulation ticks.  
+pub struct FrameStats {
+    last_frame_ms: Option<f64>,
+    frame_times_ms: VecDeque<f64>,
+    frame_timestamps_ms: VecDeque<f64>,
+    tick_timestamps_ms: VecDeque<f64>,
+}
+
+/// The calculated values for one refresh of the overlay.  
+#[derive(Debug, PartialEq)]
+pub struct FrameStatsSummary {
+    pub fps: usize,
+    pub ticks_per_second: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+    pub histogram: Vec<usize>,
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameStats {
+    /// const fn, so it can be used in a thread_local  
+    pub const fn new() -> Self {
+        FrameStats {
+            last_frame_ms: None,
+            frame_times_ms: VecDeque::new(),
+            frame_timestamps_ms: VecDeque::new(),
+            tick_timestamps_ms: VecDeque::new(),
+        }
+    }
+
+    /// Record the timestamp of a rendered frame.  
+    /// The frame time is the difference to the previous frame.  
+    pub fn record_frame(&mut self, timestamp_ms: f64) {
+        if let Some(last_frame_ms) = self.last_frame_ms {
+            if self.frame_times_ms.len() == FRAME_TIMES_CAPACITY {
+                self.frame_times_ms.pop_front();
+            }
+            self.frame_times_ms.push_back(timestamp_ms - last_frame_ms);
+        }
+        self.last_frame_ms = Some(timestamp_ms);
+        push_in_window(&mut self.frame_timestamps_ms, timestamp_ms);
+    }
+
+    /// Record the timestamp of a simulation tick.  
+    pub fn record_tick(&mut self, timestamp_ms: f64) {
+        push_in_window(&mut self.tick_timestamps_ms, timestamp_ms);
+    }
+
+    /// Calculate the values at the moment `now_ms`.  
+    pub fn summary(&self, now_ms: f64) -> FrameStatsSummary {
+        let mut sorted: Vec<f64> = self.frame_times_ms.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        let mut histogram = vec![0; FRAME_TIME_BUCKET_EDGES_MS.len() + 1];
+        for frame_time_ms in &self.frame_times_ms {
+            let bucket = FRAME_TIME_BUCKET_EDGES_MS
+                .iter()
+                .position(|edge| frame_time_ms < edge)
+                .unwrap_or(FRAME_TIME_BUCKET_EDGES_MS.len());
+            histogram[bucket] += 1;
+        }
+        // return
+        FrameStatsSummary {
+            fps: count_in_window(&self.frame_timestamps_ms, now_ms),
+            ticks_per_second: count_in_window(&self.tick_timestamps_ms, now_ms),
+            p50_ms: percentile(&sorted, 50.0),
+            p95_ms: percentile(&sorted, 95.0),
+            p99_ms: percentile(&sorted, 99.0),
+            max_ms: sorted.last().copied().unwrap_or(0.0),
+            histogram,
+        }
+    }
+}
+
+impl FrameStatsSummary {
+    /// The histogram as one line of bar characters scaled to the largest bucket.  
+    pub fn histogram_bars(&self) -> String {
+        let max_count = self.histogram.iter().copied().max().unwrap_or(0);
+        self.histogram
+            .iter()
+            .map(|count| match max_count {
+                0 => HISTOGRAM_BARS[0],
+                _ => HISTOGRAM_BARS[count * (HISTOGRAM_BARS.len() - 1) / max_count],
+            })
+            .collect()
+    }
+}
+
+/// push the timestamp and drop the ones older than the window  
+fn push_in_window(timestamps_ms: &mut VecDeque<f64>, timestamp_ms: f64) {
+    timestamps_ms.push_back(timestamp_ms);
+    while timestamps_ms.front().is_some_and(|first| timestamp_ms - first > RATE_WINDOW_MS) {
+        timestamps_ms.pop_front();
+    }
+}
+
+/// how many timestamps are in the window before `now_ms`  
+fn count_in_window(timestamps_ms: &VecDeque<f64>, now_ms: f64) -> usize {
+    timestamps_ms
+        .iter()
+        .filter(|timestamp_ms| now_ms - *timestamp_ms <= RATE_WINDOW_MS)
+        .count()
+}
+
+/// nearest-rank percentile of already sorted values  
+fn percentile(sorted: &[f64], percent: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    // return
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_frame_stats_summary() {
+        let mut frame_stats = FrameStats::new();
+        // 60 frames of 16 ms and one slow frame of 120 ms
+        let mut timestamp_ms = 0.0;
+        for _ in 0..=60 {
+            frame_stats.record_frame(timestamp_ms);
+            timestamp_ms += 16.0;
+        }
+        timestamp_ms += 104.0;
+        frame_stats.record_frame(timestamp_ms);
+        for tick in (0..10).rev() {
+            frame_stats.record_tick(timestamp_ms - tick as f64 * 100.0);
+        }
+        let summary = frame_stats.summary(timestamp_ms);
+        assert_eq!(summary.fps, 57);
+        assert_eq!(summary.ticks_per_second, 10);
+        assert_eq!(summary.p50_ms, 16.0);
+        assert_eq!(summary.p99_ms, 120.0);
+        assert_eq!(summary.max_ms, 120.0);
+        assert_eq!(summary.histogram, vec![0, 60, 0, 0, 0, 0, 1]);
+        assert_eq!(summary.histogram_bars(), "▁█▁▁▁▁▁");
+    }
+
+    #[test]
+    pub fn test_frame_stats_empty() {
+        let summary = FrameStats::new().summary(1000.0);
+        assert_eq!(summary.fps, 0);
+        assert_eq!(summary.p95_ms, 0.0);
+        assert_eq!(summary.histogram_bars(), "▁▁▁▁▁▁▁");
+    }
+}
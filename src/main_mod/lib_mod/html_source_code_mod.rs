@@ -0,0 +1,644 @@
+// src/html_source_code_mod.rs
+
+//! Pure templating of html source code. It does not use web_sys and compiles for any target.
+//! So it can be tested natively with `cargo test`.
+//! The injection into the DOM is in web_sys_mod.
+
+use crate::LibraryError;
+
+/// The prefix of every placeholder in the html source code.  
+const PLACEHOLDER_PREFIX: &str = "{ph_";
+
+/// The attribute that marks an element for incremental updates.  
+const DATA_KEY_ATTRIBUTE: &str = " data-key=\"";
+
+/// The attribute prefix that declares an event handler like `data-on-click="run"`.  
+const DATA_ON_ATTRIBUTE: &str = " data-on-";
+
+/// One change in the DOM to bring the mounted html up to date.  
+/// The element is found by its `data-key` attribute.  
+#[derive(Debug, PartialEq)]
+pub enum KeyedPatch {
+    /// Set the text content of the keyed element.
+    Text { key: String, text: String },
+    /// Set one attribute of the keyed element.
+    Attribute { key: String, name: String, value: String },
+}
+
+/// What kind of replacement was made for a placeholder.  
+#[derive(PartialEq)]
+enum BindingKind {
+    TextNode,
+    AttributeValue,
+    HtmlSourceCode,
+}
+
+/// The not encoded value of a replaced placeholder, used to compare two renders of the same template.  
+struct Binding {
+    placeholder: &'static str,
+    kind: BindingKind,
+    value: String,
+}
+
+/// HtmlSourceCode - type to manipulate HTML source code safer than with string functions only  
+/// WARNING for HTML INJECTION!   
+/// HTML is the standard markup language for Web pages. HTML source code is just a text.  
+/// It is easy to read, write, understand and parse.  
+/// The syntax of HTML source code is similar to XML structured with elements, tags, nodes, texts, comments and attributes.  
+/// The browser then transforms this HTML source code into the DOM tree and then renders that.  
+/// It is very tempting to modify this source code in our application with string manipulation and then pass it to the browser.  
+/// The html source code (it is just a string) that is provided by the programmer is always ok, he wants it to work properly.  
+/// The BIG problem arises when we need to inject some user provided data into the HTML source code.  
+/// The HTML syntax mixes instructions and data together and this creates a BIG problem.  
+/// Never put user provided strings in a html source code directly, because it can contain an HTML injection attack.  
+/// We need to encode all user data before putting it into the HTML source code.  
+/// There are 2 types of encodings: one for attributes values and another for text nodes.  
+/// We will create a new type that makes it safer and easier for the programmer to replace data in the HTML source code.  
+pub struct HtmlSourceCode {
+    template: &'static str,
+    /// The templates of the replaced fragments. Only templates from the programmer can declare event handlers.
+    fragment_templates: Vec<&'static str>,
+    html: String,
+    bindings: Vec<Binding>,
+}
+
+impl HtmlSourceCode {
+    /// The programmer provides a &'static str to initiate HtmlSourceCode.  
+    /// The html source code coming from the programmer is always ok, he wants it to work properly.  
+    /// The data that will be replaced, have a recognizable, unique and delimited value.  
+    pub fn new(html_code: &'static str) -> Self {
+        HtmlSourceCode {
+            template: html_code,
+            fragment_templates: vec![],
+            html: html_code.to_string(),
+            bindings: vec![],
+        }
+    }
+
+    /// get the html, it can still contain placeholders  
+    /// Use it for fragments. Before injecting into the DOM use finalize().  
+    pub fn get_html(&self) -> String {
+        self.html.clone()
+    }
+
+    /// get the well formed html without any leftover placeholder  
+    /// We trust the programmer to carefully work with HtmlSourceCode to be always well formed and without HTML injection.  
+    /// A leftover `{ph_...}` means the programmer forgot to replace it.  
+    pub fn finalize(&self) -> Result<String, LibraryError> {
+        if let Some(pos) = self.html.find(PLACEHOLDER_PREFIX) {
+            let rest = &self.html[pos..];
+            let placeholder = match rest.find('}') {
+                Some(end) => &rest[..=end],
+                None => rest,
+            };
+            return Err(LibraryError::LeftoverPlaceholder(placeholder.to_string()));
+        }
+        // return
+        Ok(self.html.clone())
+    }
+
+    /// This must be pure text, no html element are allowed for bold or italic...  
+    /// Returns error if the placeholder does not exist. Probably it is misspelled.  
+    pub fn replace_text_node(&mut self, placeholder: &'static str, text: &str) -> Result<(), LibraryError> {
+        self.replace_placeholder(
+            placeholder,
+            &encode_braces(&html_escape::encode_text(text)),
+            BindingKind::TextNode,
+            text,
+        )
+    }
+
+    /// The attribute value must be double_quoted.  
+    /// Returns error if the placeholder does not exist. Probably it is misspelled.  
+    pub fn replace_attribute_value(&mut self, placeholder: &'static str, value: &str) -> Result<(), LibraryError> {
+        self.replace_placeholder(
+            placeholder,
+            &encode_braces(&html_escape::encode_double_quoted_attribute(value)),
+            BindingKind::AttributeValue,
+            value,
+        )
+    }
+
+    /// For URL attributes like `href` and `src`. The attribute value must be double_quoted.  
+    /// Only http(s), relative and `#` fragment URLs are allowed, so `javascript:` and similar are rejected.  
+    /// The path, query and fragment are percent-encoded, then the attribute encoding is applied.  
+    pub fn replace_url_attribute_value(&mut self, placeholder: &'static str, url: &str) -> Result<(), LibraryError> {
+        let safe_url = sanitize_url(url)?;
+        self.replace_placeholder(
+            placeholder,
+            &encode_braces(&html_escape::encode_double_quoted_attribute(&safe_url)),
+            BindingKind::AttributeValue,
+            &safe_url,
+        )
+    }
+
+    /// We expect the HtmlSourceCode to be well formed. For that we trust the programmer.  
+    /// Returns error if the placeholder does not exist. Probably it is misspelled.  
+    pub fn replace_html_source_code(&mut self, placeholder: &'static str, html_source_code: &HtmlSourceCode) -> Result<(), LibraryError> {
+        let html = html_source_code.get_html();
+        self.replace_placeholder(placeholder, &html, BindingKind::HtmlSourceCode, &html)?;
+        self.fragment_templates.push(html_source_code.template);
+        self.fragment_templates.extend(html_source_code.fragment_templates.iter());
+        Ok(())
+    }
+
+    /// Render the fragment template once for every item and splice the result into the placeholder.  
+    /// The closure replaces the placeholders of one item with the same methods and encodings as above.  
+    /// Every rendered item must be without leftover placeholders.  
+    /// An empty iterator replaces the placeholder with an empty string.  
+    pub fn replace_repeated_fragment<I, F>(
+        &mut self,
+        placeholder: &'static str,
+        fragment_template: &'static str,
+        items: I,
+        mut fill_fragment: F,
+    ) -> Result<(), LibraryError>
+    where
+        I: IntoIterator,
+        F: FnMut(&mut HtmlSourceCode, I::Item) -> Result<(), LibraryError>,
+    {
+        let mut rendered = String::new();
+        let mut is_rendered = false;
+        for item in items {
+            let mut fragment = HtmlSourceCode::new(fragment_template);
+            fill_fragment(&mut fragment, item)?;
+            rendered.push_str(&fragment.finalize()?);
+            is_rendered = true;
+        }
+        self.replace_placeholder(placeholder, &rendered, BindingKind::HtmlSourceCode, &rendered)?;
+        // without items the fragment and its event handlers are not in the html
+        if is_rendered {
+            self.fragment_templates.push(fragment_template);
+        }
+        Ok(())
+    }
+
+    /// Private function. The replacement must be already encoded.  
+    /// The not encoded value is remembered for keyed_patches().  
+    fn replace_placeholder(
+        &mut self,
+        placeholder: &'static str,
+        encoded: &str,
+        kind: BindingKind,
+        value: &str,
+    ) -> Result<(), LibraryError> {
+        if !self.html.contains(placeholder) {
+            return Err(LibraryError::PlaceholderNotFound(placeholder.to_string()));
+        }
+        self.html = self.html.replace(placeholder, encoded);
+        self.bindings.push(Binding {
+            placeholder,
+            kind,
+            value: value.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Returns all declared event handlers as pairs of event and handler name, without duplicates.  
+    /// The template declares them like `<input type="button" data-on-click="run"/>`.  
+    /// Only the templates are parsed and not the html, because the replaced user text is not trusted.  
+    /// The handler name must be made of `[a-z0-9_]`, because it is used in a css selector.  
+    pub fn declared_event_handlers(&self) -> Result<Vec<(String, String)>, LibraryError> {
+        let mut declared: Vec<(String, String)> = vec![];
+        for template in std::iter::once(&self.template).chain(self.fragment_templates.iter()) {
+            let mut rest = *template;
+            while let Some(pos) = rest.find(DATA_ON_ATTRIBUTE) {
+                rest = &rest[pos + DATA_ON_ATTRIBUTE.len()..];
+                let Some((event, after_event)) = rest.split_once("=\"") else {
+                    break;
+                };
+                let Some((handler_name, _)) = after_event.split_once('"') else {
+                    break;
+                };
+                if event.is_empty() || !event.chars().all(|c| c.is_ascii_lowercase()) {
+                    continue;
+                }
+                let is_valid_name = !handler_name.is_empty()
+                    && handler_name
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+                if !is_valid_name {
+                    return Err(LibraryError::InvalidEventHandlerName(handler_name.to_string()));
+                }
+                if !declared.iter().any(|(e, name)| e == event && name == handler_name) {
+                    declared.push((event.to_string(), handler_name.to_string()));
+                }
+            }
+        }
+        Ok(declared)
+    }
+
+    /// Check that every declared event handler is registered and every registered handler is declared.  
+    /// A typo in the template or in the code must fail loudly and not break silently.  
+    /// Returns the declared pairs of event and handler name.  
+    pub fn check_event_handlers(&self, registered_handler_names: &[&str]) -> Result<Vec<(String, String)>, LibraryError> {
+        let declared = self.declared_event_handlers()?;
+        if let Some((_event, handler_name)) = declared
+            .iter()
+            .find(|(_event, name)| !registered_handler_names.contains(&name.as_str()))
+        {
+            return Err(LibraryError::MissingEventHandler(handler_name.to_string()));
+        }
+        if let Some(handler_name) = registered_handler_names
+            .iter()
+            .find(|registered| !declared.iter().any(|(_event, name)| name == *registered))
+        {
+            return Err(LibraryError::UnusedEventHandler(handler_name.to_string()));
+        }
+        Ok(declared)
+    }
+
+    /// Compare with the html source code already mounted in the DOM and return only the changed values.  
+    /// Both must be rendered from the same template.  
+    /// A changed placeholder must be the only content of an element with the `data-key` attribute  
+    /// or an attribute value of such an element. Else it returns the error NotKeyed and the caller must inject the whole html.  
+    /// A changed placeholder used more than once in the template returns the error DuplicateKeyedPlaceholder,  
+    /// because only its first occurrence would be patched.  
+    pub fn keyed_patches(&self, mounted: &HtmlSourceCode) -> Result<Vec<KeyedPatch>, LibraryError> {
+        if self.template != mounted.template {
+            return Err(LibraryError::TemplateMismatch);
+        }
+        let mut patches = vec![];
+        for binding in self.bindings.iter() {
+            let is_unchanged = mounted
+                .bindings
+                .iter()
+                .any(|old| old.placeholder == binding.placeholder && old.value == binding.value);
+            if is_unchanged {
+                continue;
+            }
+            let not_keyed = || LibraryError::NotKeyed(binding.placeholder.to_string());
+            if self.template.matches(binding.placeholder).count() > 1 {
+                return Err(LibraryError::DuplicateKeyedPlaceholder(binding.placeholder.to_string()));
+            }
+            match binding.kind {
+                BindingKind::TextNode => {
+                    let key = keyed_text_node(self.template, binding.placeholder).ok_or_else(not_keyed)?;
+                    patches.push(KeyedPatch::Text {
+                        key,
+                        text: binding.value.clone(),
+                    });
+                }
+                BindingKind::AttributeValue => {
+                    let (key, name) = keyed_attribute(self.template, binding.placeholder).ok_or_else(not_keyed)?;
+                    patches.push(KeyedPatch::Attribute {
+                        key,
+                        name,
+                        value: binding.value.clone(),
+                    });
+                }
+                BindingKind::HtmlSourceCode => return Err(not_keyed()),
+            }
+        }
+        // return
+        Ok(patches)
+    }
+}
+
+/// The encoders of html_escape leave `{` as it is, so a value like `{ph_name}` would look like a placeholder.  
+/// After encoding it can never be found by finalize() or replaced by a later replace_* call.  
+fn encode_braces(encoded: &str) -> String {
+    encoded.replace('{', "&#123;")
+}
+
+/// Returns the data-key of the element if the placeholder is its only content like `<span data-key="score">{ph_score}</span>`.  
+fn keyed_text_node(template: &str, placeholder: &str) -> Option<String> {
+    let pos = template.find(placeholder)?;
+    let before = &template[..pos];
+    let after = &template[pos + placeholder.len()..];
+    if !before.ends_with('>') || !after.starts_with("</") {
+        return None;
+    }
+    let tag_start = before.rfind('<')?;
+    data_key(&before[tag_start..])
+}
+
+/// Returns the data-key and the attribute name if the placeholder is an attribute value like  
+/// `<div data-key="bar" style="{ph_style}">`.  
+fn keyed_attribute(template: &str, placeholder: &str) -> Option<(String, String)> {
+    let pos = template.find(placeholder)?;
+    let before = &template[..pos];
+    let tag_start = before.rfind('<')?;
+    let tag_end = pos + template[pos..].find('>')?;
+    let before_value = before.strip_suffix("=\"")?;
+    let name_start = before_value.rfind(char::is_whitespace)? + 1;
+    let name = before_value[name_start..].to_string();
+    Some((data_key(&template[tag_start..tag_end])?, name))
+}
+
+/// Returns the value of the data-key attribute inside the tag.  
+fn data_key(tag: &str) -> Option<String> {
+    let start = tag.find(DATA_KEY_ATTRIBUTE)? + DATA_KEY_ATTRIBUTE.len();
+    let end = start + tag[start..].find('"')?;
+    Some(tag[start..end].to_string())
+}
+
+/// Returns the url with percent-encoded path, query and fragment, or error if the url is not allowed.  
+/// The browser ignores leading and trailing control characters and removes tabs and newlines anywhere in the url.  
+/// We do the same before checking the scheme, so `java\tscript:` cannot sneak through.  
+fn sanitize_url(url: &str) -> Result<String, LibraryError> {
+    let unsafe_url = || LibraryError::UnsafeUrl(url.to_string());
+    let url: String = url
+        .trim_matches(|c: char| c <= ' ')
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .collect();
+
+    // the scheme ends with the first colon, but only if it is before any of /?#
+    let scheme_end = url.find([':', '/', '?', '#']).filter(|pos| url[*pos..].starts_with(':'));
+    let (prefix, rest) = match scheme_end {
+        Some(pos) => {
+            let scheme = url[..pos].to_ascii_lowercase();
+            if scheme != "http" && scheme != "https" {
+                return Err(unsafe_url());
+            }
+            let Some(after_slashes) = url[pos + 1..].strip_prefix("//") else {
+                return Err(unsafe_url());
+            };
+            let authority_end = after_slashes.find(['/', '?', '#']).unwrap_or(after_slashes.len());
+            let authority = &after_slashes[..authority_end];
+            let is_valid_authority = !authority.is_empty()
+                && authority
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | ':' | '@' | '[' | ']'));
+            if !is_valid_authority {
+                return Err(unsafe_url());
+            }
+            (format!("{scheme}://{authority}"), &after_slashes[authority_end..])
+        }
+        None => {
+            // protocol-relative url can point to any host
+            if url.starts_with("//") {
+                return Err(unsafe_url());
+            }
+            (String::new(), url.as_str())
+        }
+    };
+
+    let (path, query_and_fragment) = rest.split_at(rest.find(['?', '#']).unwrap_or(rest.len()));
+    let mut safe_url = prefix;
+    safe_url.push_str(&percent_encode(path, "/"));
+    safe_url.push_str(&percent_encode(query_and_fragment, "/?#"));
+    // return
+    Ok(safe_url)
+}
+
+/// Percent-encode all except unreserved, sub-delims, `:@` and the extra allowed characters.  
+/// Already encoded `%XX` sequences are left as they are.  
+fn percent_encode(text: &str, extra_allowed: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut encoded = String::new();
+    for (i, byte) in bytes.iter().enumerate() {
+        let c = *byte as char;
+        let is_escape_sequence = c == '%' && bytes.len() > i + 2 && bytes[i + 1].is_ascii_hexdigit() && bytes[i + 2].is_ascii_hexdigit();
+        if is_escape_sequence || c.is_ascii_alphanumeric() || "-._~!$&'()*+,;=:@".contains(c) || extra_allowed.contains(c) {
+            encoded.push(c);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_replace_missing_placeholder() {
+        let mut html_source_code = HtmlSourceCode::new(r#"<p>{ph_text_1}</p>"#);
+        assert!(html_source_code.replace_text_node("{ph_txt_1}", "x").is_err());
+        assert!(html_source_code.replace_text_node("{ph_text_1}", "<b>").is_ok());
+        assert_eq!(html_source_code.finalize().expect("error"), "<p>&lt;b&gt;</p>");
+    }
+
+    #[test]
+    pub fn test_replace_value_that_looks_like_placeholder() {
+        let mut html_source_code = HtmlSourceCode::new(r#"<p title="{ph_title}">{ph_a}</p><p>{ph_b}</p>"#);
+        html_source_code.replace_attribute_value("{ph_title}", "{ph_b}").expect("error");
+        html_source_code.replace_text_node("{ph_a}", "{ph_b}").expect("error");
+        html_source_code.replace_text_node("{ph_b}", "b").expect("error");
+        assert_eq!(
+            html_source_code.finalize().expect("error"),
+            r#"<p title="&#123;ph_b}">&#123;ph_b}</p><p>b</p>"#
+        );
+
+        // a value with a placeholder that nobody replaces is not a leftover placeholder
+        let mut html_source_code = HtmlSourceCode::new(r#"<p>{ph_a}</p>"#);
+        html_source_code
+            .replace_text_node("{ph_a}", "Placeholder `{ph_x}` not found")
+            .expect("error");
+        assert_eq!(
+            html_source_code.finalize().expect("error"),
+            "<p>Placeholder `&#123;ph_x}` not found</p>"
+        );
+    }
+
+    #[test]
+    pub fn test_replace_repeated_fragment() {
+        let mut html_source_code = HtmlSourceCode::new(r#"<table>{ph_rows}</table>"#);
+        let rows = [("alice", 30), ("<bob>", 20)];
+        html_source_code
+            .replace_repeated_fragment(
+                "{ph_rows}",
+                r#"<tr><td>{ph_name}</td><td>{ph_score}</td></tr>"#,
+                rows.iter(),
+                |fragment, (name, score)| {
+                    fragment.replace_text_node("{ph_name}", name)?;
+                    fragment.replace_text_node("{ph_score}", &score.to_string())
+                },
+            )
+            .expect("error");
+        assert_eq!(
+            html_source_code.finalize().expect("error"),
+            "<table><tr><td>alice</td><td>30</td></tr><tr><td>&lt;bob&gt;</td><td>20</td></tr></table>"
+        );
+
+        let mut html_source_code = HtmlSourceCode::new(r#"<ul>{ph_items}</ul>"#);
+        let result = html_source_code.replace_repeated_fragment("{ph_items}", "<li>{ph_item}</li>", ["a"], |_fragment, _item| Ok(()));
+        assert!(matches!(result, Err(LibraryError::LeftoverPlaceholder(_))));
+    }
+
+    #[test]
+    pub fn test_replace_url_attribute_value() {
+        let render = |url: &str| {
+            let mut html_source_code = HtmlSourceCode::new(r#"<a href="{ph_href}">link</a>"#);
+            html_source_code
+                .replace_url_attribute_value("{ph_href}", url)
+                .map(|_| html_source_code.get_html())
+        };
+        assert_eq!(
+            render("https://bestia.dev/a b").expect("error"),
+            r#"<a href="https://bestia.dev/a%20b">link</a>"#
+        );
+        assert_eq!(
+            render("HTTP://bestia.dev").expect("error"),
+            r#"<a href="http://bestia.dev">link</a>"#
+        );
+        assert_eq!(
+            render("/snake_bevy_wasm_pwa/#print/world").expect("error"),
+            r#"<a href="/snake_bevy_wasm_pwa/#print/world">link</a>"#
+        );
+        assert_eq!(render("#help").expect("error"), r##"<a href="#help">link</a>"##);
+        assert_eq!(
+            render("replay/č?a=1&b=2").expect("error"),
+            r#"<a href="replay/%C4%8D?a=1&amp;b=2">link</a>"#
+        );
+        assert_eq!(render("50%25").expect("error"), r#"<a href="50%25">link</a>"#);
+    }
+
+    #[test]
+    pub fn test_replace_url_attribute_value_xss_vectors() {
+        let render = |url: &str| {
+            let mut html_source_code = HtmlSourceCode::new(r#"<a href="{ph_href}">link</a>"#);
+            html_source_code
+                .replace_url_attribute_value("{ph_href}", url)
+                .map(|_| html_source_code.get_html())
+        };
+        let rejected = [
+            "javascript:alert(1)",
+            "JaVaScRiPt:alert(1)",
+            " javascript:alert(1)",
+            "\u{1}javascript:alert(1)",
+            "java\tscript:alert(1)",
+            "java\nscript:alert(1)",
+            "javascript\r:alert(1)",
+            "vbscript:msgbox(1)",
+            "data:text/html;base64,PHNjcmlwdD5hbGVydCgxKTwvc2NyaXB0Pg==",
+            "https:alert(1)",
+            "https://bestia.dev\"onmouseover=\"alert(1)",
+            "//evil.example",
+            "https://",
+        ];
+        for url in rejected {
+            assert!(matches!(render(url), Err(LibraryError::UnsafeUrl(_))), "not rejected: {url:?}");
+        }
+        // these are relative paths, but the quotes and brackets must never break out of the attribute
+        assert_eq!(
+            render(r#""><script>alert(1)</script>"#).expect("error"),
+            r#"<a href="%22%3E%3Cscript%3Ealert(1)%3C/script%3E">link</a>"#
+        );
+        assert_eq!(
+            render("javascript&colon;alert(1)").expect("error"),
+            r#"<a href="javascript&amp;colon;alert(1)">link</a>"#
+        );
+        assert_eq!(
+            render("\\\\evil.example").expect("error"),
+            r#"<a href="%5C%5Cevil.example">link</a>"#
+        );
+    }
+
+    #[test]
+    pub fn test_keyed_patches() {
+        let template = r#"<div data-key="bar" style="{ph_style}"><span data-key="score">{ph_score}</span> {ph_name}</div>"#;
+        let render = |style: &str, score: &str, name: &str| {
+            let mut html_source_code = HtmlSourceCode::new(template);
+            html_source_code.replace_attribute_value("{ph_style}", style).expect("error");
+            html_source_code.replace_text_node("{ph_score}", score).expect("error");
+            html_source_code.replace_text_node("{ph_name}", name).expect("error");
+            html_source_code
+        };
+        let mounted = render("width:10%", "1", "alice");
+        assert_eq!(render("width:10%", "1", "alice").keyed_patches(&mounted).expect("error"), vec![]);
+        assert_eq!(
+            render("width:20%", "<2>", "alice").keyed_patches(&mounted).expect("error"),
+            vec![
+                KeyedPatch::Attribute {
+                    key: "bar".to_string(),
+                    name: "style".to_string(),
+                    value: "width:20%".to_string()
+                },
+                KeyedPatch::Text {
+                    key: "score".to_string(),
+                    text: "<2>".to_string()
+                },
+            ]
+        );
+        // {ph_name} is not the only content of a keyed element
+        assert!(matches!(
+            render("width:10%", "1", "bob").keyed_patches(&mounted),
+            Err(LibraryError::NotKeyed(_))
+        ));
+        assert!(matches!(
+            HtmlSourceCode::new("<p></p>").keyed_patches(&mounted),
+            Err(LibraryError::TemplateMismatch)
+        ));
+
+        // the same keyed placeholder twice must not patch only the first occurrence
+        let template = r#"<span data-key="score">{ph_score}</span><span data-key="score_copy">{ph_score}</span>"#;
+        let render = |score: &str| {
+            let mut html_source_code = HtmlSourceCode::new(template);
+            html_source_code.replace_text_node("{ph_score}", score).expect("error");
+            html_source_code
+        };
+        assert_eq!(render("1").keyed_patches(&render("1")).expect("error"), vec![]);
+        assert!(matches!(
+            render("2").keyed_patches(&render("1")),
+            Err(LibraryError::DuplicateKeyedPlaceholder(_))
+        ));
+    }
+
+    #[test]
+    pub fn test_check_event_handlers() {
+        let html_source_code = HtmlSourceCode::new(
+            r#"<input type="button" id="btn_run" data-on-click="run"/><input type="text" data-on-change="name_changed"/>"#,
+        );
+        assert_eq!(
+            html_source_code.check_event_handlers(&["run", "name_changed"]).expect("error"),
+            vec![
+                ("click".to_string(), "run".to_string()),
+                ("change".to_string(), "name_changed".to_string())
+            ]
+        );
+        assert!(matches!(
+            html_source_code.check_event_handlers(&["run"]),
+            Err(LibraryError::MissingEventHandler(name)) if name == "name_changed"
+        ));
+        assert!(matches!(
+            html_source_code.check_event_handlers(&["run", "name_changed", "stop"]),
+            Err(LibraryError::UnusedEventHandler(name)) if name == "stop"
+        ));
+    }
+
+    #[test]
+    pub fn test_declared_event_handlers_only_from_templates() {
+        // the user text looks like a declaration, but it is not in the template
+        let mut html_source_code = HtmlSourceCode::new(r#"<p>{ph_text}</p><div>{ph_fragment}</div><ul>{ph_rows}</ul>"#);
+        html_source_code
+            .replace_text_node("{ph_text}", r#" data-on-click="x""#)
+            .expect("error");
+        html_source_code
+            .replace_html_source_code("{ph_fragment}", &HtmlSourceCode::new(r#"<input data-on-click="run"/>"#))
+            .expect("error");
+        html_source_code
+            .replace_repeated_fragment(
+                "{ph_rows}",
+                r#"<li data-on-click="select">{ph_name}</li>"#,
+                ["a", "b"],
+                |fragment, name| fragment.replace_text_node("{ph_name}", name),
+            )
+            .expect("error");
+        assert_eq!(
+            html_source_code.check_event_handlers(&["run", "select"]).expect("error"),
+            vec![
+                ("click".to_string(), "run".to_string()),
+                ("click".to_string(), "select".to_string())
+            ]
+        );
+
+        let html_source_code = HtmlSourceCode::new(r#"<input data-on-click="run],*[x"/>"#);
+        assert!(matches!(
+            html_source_code.check_event_handlers(&["run"]),
+            Err(LibraryError::InvalidEventHandlerName(_))
+        ));
+    }
+
+    #[test]
+    pub fn test_finalize_leftover_placeholder() {
+        let html_source_code = HtmlSourceCode::new(r#"<p class="{ph_attr_1}">text</p>"#);
+        match html_source_code.finalize() {
+            Err(LibraryError::LeftoverPlaceholder(placeholder)) => assert_eq!(placeholder, "{ph_attr_1}"),
+            _ => panic!("expected LeftoverPlaceholder"),
+        }
+    }
+}
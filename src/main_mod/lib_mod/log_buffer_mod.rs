@@ -0,0 +1,107 @@
+// src/log_buffer_mod.rs
+
+//! Ring buffer of the last log records for the in-app log console.
+//! Testers on phones cannot open the devtools console, so the app keeps the last records in memory.
+//! This module is UI agnostic and can be tested natively.
+
+use std::collections::VecDeque;
+
+/// How many log records are kept in memory.
+pub const LOG_RING_BUFFER_CAPACITY: usize = 500;
+
+/// One log record already converted to strings.  
+pub struct LogEntry {
+    pub time: String,
+    pub level: log::Level,
+    pub target: String,
+    pub message: String,
+}
+
+impl LogEntry {
+    /// format as one line of text for the download  
+    pub fn format_line(&self) -> String {
+        format!("{} {:<5} [{}] {}", self.time, self.level, self.target, self.message)
+    }
+}
+
+/// Keeps only the last `capacity` log entries. The oldest are dropped first.  
+pub struct LogRingBuffer {
+    capacity: usize,
+    entries: VecDeque<LogEntry>,
+}
+
+impl LogRingBuffer {
+    /// const fn, so it can be used in a static  
+    pub const fn new(capacity: usize) -> Self {
+        LogRingBuffer {
+            capacity,
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// add the entry and drop the oldest if the buffer is full  
+    pub fn push(&mut self, entry: LogEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// entries with the level equal or more severe than the filter, from the oldest to the newest  
+    pub fn filtered(&self, level_filter: log::LevelFilter) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter().filter(move |entry| entry.level <= level_filter)
+    }
+
+    /// all filtered entries as text, one line per entry  
+    pub fn to_text(&self, level_filter: log::LevelFilter) -> String {
+        let mut text = String::new();
+        for entry in self.filtered(level_filter) {
+            text.push_str(&entry.format_line());
+            text.push('\n');
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(level: log::Level, message: &str) -> LogEntry {
+        LogEntry {
+            time: "10:00:00".to_string(),
+            level,
+            target: "snake".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    pub fn test_ring_buffer_drops_oldest() {
+        let mut log_ring_buffer = LogRingBuffer::new(2);
+        log_ring_buffer.push(entry(log::Level::Info, "one"));
+        log_ring_buffer.push(entry(log::Level::Info, "two"));
+        log_ring_buffer.push(entry(log::Level::Info, "three"));
+        let messages: Vec<&str> = log_ring_buffer
+            .filtered(log::LevelFilter::Trace)
+            .map(|e| e.message.as_str())
+            .collect();
+        assert_eq!(messages, vec!["two", "three"]);
+    }
+
+    #[test]
+    pub fn test_filter_and_format() {
+        let mut log_ring_buffer = LogRingBuffer::new(10);
+        log_ring_buffer.push(entry(log::Level::Debug, "details"));
+        log_ring_buffer.push(entry(log::Level::Warn, "careful"));
+        log_ring_buffer.push(entry(log::Level::Error, "broken"));
+        assert_eq!(
+            log_ring_buffer.to_text(log::LevelFilter::Warn),
+            "10:00:00 WARN  [snake] careful\n10:00:00 ERROR [snake] broken\n"
+        );
+        assert_eq!(log_ring_buffer.filtered(log::LevelFilter::Off).count(), 0);
+    }
+}
@@ -4,8 +4,6 @@
 //! Trying to isolate/hide all javascript code and conversion in this module.  
 
 // region: use
-// the macro unwrap! shows the TRUE location where the error has ocurred.
-use unwrap::unwrap;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 // use wasm_bindgen::JsValue;
@@ -14,80 +12,432 @@ use wasm_bindgen::JsCast;
 // use web_sys::{Request, RequestInit, Response};
 // endregion: use
 
-mod html_source_code_mod;
-//re-export
-pub use html_source_code_mod::HtmlSourceCode;
+use super::HtmlSourceCode;
+use super::KeyedPatch;
+use super::{LogEntry, LogRingBuffer, LOG_RING_BUFFER_CAPACITY};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+thread_local! {
+    /// The last html source code injected or updated in the element with this id.
+    static MOUNTED_HTML_SOURCE_CODE: RefCell<HashMap<String, HtmlSourceCode>> = RefCell::new(HashMap::new());
+    /// Counter of DOM writes to measure the effect of incremental updates.
+    static DOM_WRITE_COUNT: Cell<u32> = const { Cell::new(0) };
+}
+
+/// The last log records for the in-app log console.  
+/// The logger must be Sync, so this is a Mutex and not a thread_local.  
+static LOG_RING_BUFFER: std::sync::Mutex<LogRingBuffer> = std::sync::Mutex::new(LogRingBuffer::new(LOG_RING_BUFFER_CAPACITY));
+
+/// Logger that writes to the browser console and keeps the last records in LOG_RING_BUFFER.  
+struct BufferedConsoleLogger;
+
+static BUFFERED_CONSOLE_LOGGER: BufferedConsoleLogger = BufferedConsoleLogger;
+
+impl log::Log for BufferedConsoleLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let log_entry = LogEntry {
+            time: now_time_as_string(),
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+        let line = JsValue::from_str(&log_entry.format_line());
+        match record.level() {
+            log::Level::Error => web_sys::console::error_1(&line),
+            log::Level::Warn => web_sys::console::warn_1(&line),
+            log::Level::Info => web_sys::console::info_1(&line),
+            log::Level::Debug | log::Level::Trace => web_sys::console::debug_1(&line),
+        }
+        if let Ok(mut log_ring_buffer) = LOG_RING_BUFFER.lock() {
+            log_ring_buffer.push(log_entry);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Initialize the logger for the console and the in-app log console.  
+pub fn init_logger(max_level: log::LevelFilter) {
+    if log::set_logger(&BUFFERED_CONSOLE_LOGGER).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+/// Read the last log records inside the closure.  
+pub fn with_log_ring_buffer<R>(read_log_ring_buffer: impl FnOnce(&LogRingBuffer) -> R) -> R {
+    match LOG_RING_BUFFER.lock() {
+        Ok(log_ring_buffer) => read_log_ring_buffer(&log_ring_buffer),
+        // a panic while logging poisons the mutex, but the records are still usable
+        Err(poisoned) => read_log_ring_buffer(&poisoned.into_inner()),
+    }
+}
+
+/// count one DOM write  
+fn count_dom_write() {
+    DOM_WRITE_COUNT.set(DOM_WRITE_COUNT.get() + 1);
+}
+
+/// number of DOM writes since the start of the app  
+pub fn dom_write_count() -> u32 {
+    DOM_WRITE_COUNT.get()
+}
+
+/// all possible errors of web_sys_mod  
+/// They convert into LibraryError and anyhow, so the caller can show a friendly message instead of a panic.  
+#[derive(thiserror::Error, Debug)]
+pub enum WebSysError {
+    #[error("The global window object does not exist.")]
+    NoWindow,
+    #[error("The document does not exist.")]
+    NoDocument,
+    #[error("Element with id `{0}` does not exist.")]
+    ElementNotFound(String),
+    #[error("Element with id `{0}` is not {1}.")]
+    WrongElementType(String, &'static str),
+    #[error("JavaScript error: {0}")]
+    JavaScript(String),
+}
+
+impl WebSysError {
+    /// Stable error code to recognize the error in bug reports.  
+    pub fn code(&self) -> &'static str {
+        match self {
+            WebSysError::NoWindow => "E300",
+            WebSysError::NoDocument => "E301",
+            WebSysError::ElementNotFound(_) => "E302",
+            WebSysError::WrongElementType(_, _) => "E303",
+            WebSysError::JavaScript(_) => "E304",
+        }
+    }
+}
+
+impl From<JsValue> for WebSysError {
+    fn from(js_value: JsValue) -> Self {
+        WebSysError::JavaScript(js_value.as_string().unwrap_or_else(|| format!("{js_value:?}")))
+    }
+}
 
 /// return the global window object  
-pub fn window() -> web_sys::Window {
-    unwrap!(web_sys::window())
+pub fn window() -> Result<web_sys::Window, WebSysError> {
+    web_sys::window().ok_or(WebSysError::NoWindow)
 }
 
 /// get element by id  
-pub fn get_element_by_id(element_id: &str) -> web_sys::Element {
-    let document = unwrap!(window().document());
-    unwrap!(document.get_element_by_id(element_id))
+pub fn get_element_by_id(element_id: &str) -> Result<web_sys::Element, WebSysError> {
+    let document = window()?.document().ok_or(WebSysError::NoDocument)?;
+    document
+        .get_element_by_id(element_id)
+        .ok_or_else(|| WebSysError::ElementNotFound(element_id.to_string()))
 }
 
 /// get html element by id  
-pub fn get_html_element_by_id(element_id: &str) -> web_sys::HtmlElement {
-    let element = get_element_by_id(element_id);
-    let html_element: web_sys::HtmlElement = unwrap!(element.dyn_into::<web_sys::HtmlElement>());
-    //return
-    html_element
+pub fn get_html_element_by_id(element_id: &str) -> Result<web_sys::HtmlElement, WebSysError> {
+    let element = get_element_by_id(element_id)?;
+    element
+        .dyn_into::<web_sys::HtmlElement>()
+        .map_err(|_| WebSysError::WrongElementType(element_id.to_string(), "an HtmlElement"))
 }
 
 /// get input element value string by id  
-pub fn get_input_element_value_string_by_id(element_id: &str) -> String {
-    // debug_write("before get_element_by_id");
-    let input_element = get_element_by_id(element_id);
-    // debug_write("before dyn_into");
-    let input_html_element = unwrap!(input_element.dyn_into::<web_sys::HtmlInputElement>());
-    // debug_write("before value()");
-    input_html_element.value()
+pub fn get_input_element_value_string_by_id(element_id: &str) -> Result<String, WebSysError> {
+    let input_element = get_element_by_id(element_id)?;
+    let input_html_element = input_element
+        .dyn_into::<web_sys::HtmlInputElement>()
+        .map_err(|_| WebSysError::WrongElementType(element_id.to_string(), "an HtmlInputElement"))?;
+    Ok(input_html_element.value())
 }
 
-/// add event listener for button  
-pub fn add_listener_to_button(element_id: &str, fn_on_click_button: &'static (dyn Fn() + 'static)) {
+/// add event listener for onhashchange  
+pub fn add_listener_for_onhashchange(fn_on_hash_change: &'static (dyn Fn() + 'static)) -> Result<(), WebSysError> {
     let handler_1 = Box::new(move || {
-        fn_on_click_button();
+        fn_on_hash_change();
     }) as Box<dyn FnMut()>;
     let closure = Closure::wrap(handler_1);
 
-    let html_element = get_html_element_by_id(element_id);
-    html_element.set_onclick(Some(closure.as_ref().unchecked_ref()));
+    window()?.set_onhashchange(Some(closure.as_ref().unchecked_ref()));
     closure.forget();
+    Ok(())
 }
 
-/// add event listener for onhashchange  
-pub fn add_listener_for_onhashchange(fn_on_hash_change: &'static (dyn Fn() + 'static)) {
+/// set inner text  
+pub fn set_html_element_inner_text(element_id: &str, inner_text: &str) -> Result<(), WebSysError> {
+    let html_element = get_html_element_by_id(element_id)?;
+    forget_mounted_inside(&html_element)?;
+    html_element.set_inner_text(inner_text);
+    count_dom_write();
+    Ok(())
+}
+
+/// Injects the HtmlSourceCode into a DOM element.  
+/// We trust the programmer to carefully work with HtmlSourceCode to be always well formed and without HTML injection.  
+/// Returns error before injecting, if there is a leftover placeholder. The caller shows it with show_error().  
+pub fn inject_html_source_code(element_id: &str, html_source_code: HtmlSourceCode) -> Result<(), crate::LibraryError> {
+    let html = html_source_code.finalize()?;
+    let html_element = get_element_by_id(element_id)?;
+    forget_mounted_inside(&html_element)?;
+    html_element.set_inner_html(&html);
+    count_dom_write();
+    MOUNTED_HTML_SOURCE_CODE.with_borrow_mut(|mounted| {
+        mounted.insert(element_id.to_string(), html_source_code);
+    });
+    Ok(())
+}
+
+/// The new content replaces the element and all its descendants, so forget their mounted html source code.  
+/// The other mounted elements like the HUD and the debug overlay keep their incremental updates.  
+fn forget_mounted_inside(element: &web_sys::Element) -> Result<(), WebSysError> {
+    let document = window()?.document().ok_or(WebSysError::NoDocument)?;
+    MOUNTED_HTML_SOURCE_CODE.with_borrow_mut(|mounted| {
+        mounted.retain(|mounted_id, _| match document.get_element_by_id(mounted_id) {
+            Some(mounted_element) => !element.contains(Some(&mounted_element)),
+            // already removed from the DOM
+            None => false,
+        })
+    });
+    Ok(())
+}
+
+/// Injects the HtmlSourceCode and wires the event handlers declared in the template with `data-on-click="run"`.  
+/// The handlers are pairs of name and function. This replaces the manual matching of id strings.  
+/// Returns error before injecting, if a declared handler is missing or a registered handler is unused.  
+pub fn inject_html_source_code_with_handlers(
+    element_id: &str,
+    html_source_code: HtmlSourceCode,
+    handlers: &[(&str, &'static (dyn Fn() + 'static))],
+) -> Result<(), crate::LibraryError> {
+    let handler_names: Vec<&str> = handlers.iter().map(|(name, _)| *name).collect();
+    let declared = html_source_code.check_event_handlers(&handler_names)?;
+    inject_html_source_code(element_id, html_source_code)?;
+
+    let element = get_element_by_id(element_id)?;
+    for (event, handler_name) in declared.iter() {
+        let Some((_, fn_handler)) = handlers.iter().find(|(name, _)| name == handler_name) else {
+            return Err(crate::LibraryError::MissingEventHandler(handler_name.to_string()));
+        };
+        let fn_handler: &'static (dyn Fn() + 'static) = *fn_handler;
+        let node_list = element
+            .query_selector_all(&format!(r#"[data-on-{event}="{handler_name}"]"#))
+            .map_err(WebSysError::from)?;
+        for i in 0..node_list.length() {
+            let handler_1 = Box::new(move || {
+                fn_handler();
+            }) as Box<dyn FnMut()>;
+            let closure = Closure::wrap(handler_1);
+            let Some(node) = node_list.item(i) else {
+                continue;
+            };
+            node.add_event_listener_with_callback(event, closure.as_ref().unchecked_ref())
+                .map_err(WebSysError::from)?;
+            closure.forget();
+        }
+    }
+    Ok(())
+}
+
+/// Updates only the changed keyed text nodes and attributes of the html source code already mounted in the element.  
+/// This does not lose focus, input values and scroll position and does not flicker.  
+/// If nothing is mounted or the changes are not keyed, it falls back to inject_html_source_code().  
+pub fn update_html_source_code(element_id: &str, html_source_code: HtmlSourceCode) -> Result<(), crate::LibraryError> {
+    let patches = MOUNTED_HTML_SOURCE_CODE.with_borrow(|mounted| {
+        mounted
+            .get(element_id)
+            .and_then(|mounted_html_source_code| html_source_code.keyed_patches(mounted_html_source_code).ok())
+    });
+    let Some(patches) = patches else {
+        return inject_html_source_code(element_id, html_source_code);
+    };
+
+    let element = get_element_by_id(element_id)?;
+    for patch in patches.iter() {
+        let key = match patch {
+            KeyedPatch::Text { key, .. } => key,
+            KeyedPatch::Attribute { key, .. } => key,
+        };
+        let Ok(Some(keyed_element)) = element.query_selector(&format!(r#"[data-key="{key}"]"#)) else {
+            // the DOM was changed by somebody else
+            return inject_html_source_code(element_id, html_source_code);
+        };
+        match patch {
+            KeyedPatch::Text { text, .. } => keyed_element.set_text_content(Some(text)),
+            KeyedPatch::Attribute { name, value, .. } => keyed_element.set_attribute(name, value).map_err(WebSysError::from)?,
+        }
+        count_dom_write();
+    }
+    MOUNTED_HTML_SOURCE_CODE.with_borrow_mut(|mounted| {
+        mounted.insert(element_id.to_string(), html_source_code);
+    });
+    Ok(())
+}
+
+/// call the function repeatedly every timeout_ms milliseconds, returns the interval id  
+pub fn set_interval(timeout_ms: i32, fn_on_interval: &'static (dyn Fn() + 'static)) -> Result<i32, WebSysError> {
     let handler_1 = Box::new(move || {
-        fn_on_hash_change();
+        fn_on_interval();
     }) as Box<dyn FnMut()>;
     let closure = Closure::wrap(handler_1);
 
-    window().set_onhashchange(Some(closure.as_ref().unchecked_ref()));
+    let interval_id = window()?.set_interval_with_callback_and_timeout_and_arguments_0(closure.as_ref().unchecked_ref(), timeout_ms)?;
     closure.forget();
+    Ok(interval_id)
 }
 
-/// set inner text  
-pub fn set_html_element_inner_text(element_id: &str, inner_text: &str) {
-    let html_element = get_html_element_by_id(element_id);
-    html_element.set_inner_text(inner_text);
+/// stop the repeated calls started with set_interval  
+pub fn clear_interval(interval_id: i32) -> Result<(), WebSysError> {
+    window()?.clear_interval_with_handle(interval_id);
+    Ok(())
+}
+
+/// The animation frame closure that requests the next frame with itself.  
+type SharedFrameClosure = Rc<RefCell<Option<Closure<dyn FnMut(f64)>>>>;
+
+/// The running animation frame loop. Keep it to cancel the loop.  
+pub struct AnimationFrameLoop {
+    closure: SharedFrameClosure,
+    request_id: Rc<Cell<i32>>,
+}
+
+impl AnimationFrameLoop {
+    /// Cancel the requested frame, so the loop stops before the next frame.  
+    /// It must not be called from inside the frame function.  
+    pub fn cancel(self) -> Result<(), WebSysError> {
+        window()?.cancel_animation_frame(self.request_id.get())?;
+        // the closure holds a clone of its own Rc, take it to break the cycle
+        self.closure.borrow_mut().take();
+        Ok(())
+    }
+}
+
+/// Calls the function before every repaint with the timestamp in milliseconds.  
+/// The loop stops when the function returns false or when the returned loop is canceled.  
+pub fn start_animation_frame_loop(fn_on_frame: &'static (dyn Fn(f64) -> bool + 'static)) -> Result<AnimationFrameLoop, WebSysError> {
+    // the closure must request the next frame with itself, so it is shared
+    let closure: SharedFrameClosure = Rc::new(RefCell::new(None));
+    let request_id = Rc::new(Cell::new(0));
+    let closure_clone = closure.clone();
+    let request_id_clone = request_id.clone();
+    *closure.borrow_mut() = Some(Closure::wrap(Box::new(move |timestamp_ms: f64| {
+        // the closure must not be dropped while it runs, so it just does not request the next frame
+        if !fn_on_frame(timestamp_ms) {
+            return;
+        }
+        if let Some(closure) = closure_clone.borrow().as_ref() {
+            match request_animation_frame(closure) {
+                Ok(id) => request_id_clone.set(id),
+                Err(err) => log::error!("{err}"),
+            }
+        }
+    }) as Box<dyn FnMut(f64)>));
+    if let Some(closure) = closure.borrow().as_ref() {
+        request_id.set(request_animation_frame(closure)?);
+    }
+    Ok(AnimationFrameLoop { closure, request_id })
+}
+
+/// request one animation frame  
+fn request_animation_frame(closure: &Closure<dyn FnMut(f64)>) -> Result<i32, WebSysError> {
+    Ok(window()?.request_animation_frame(closure.as_ref().unchecked_ref())?)
+}
+
+/// High resolution time in milliseconds. The same clock as the animation frame timestamp.  
+pub fn performance_now() -> Result<f64, WebSysError> {
+    let performance = window()?
+        .performance()
+        .ok_or_else(|| WebSysError::JavaScript("performance is not available".to_string()))?;
+    Ok(performance.now())
+}
+
+/// The size of the wasm linear memory in bytes.  
+pub fn wasm_memory_bytes() -> Result<u32, WebSysError> {
+    let memory: js_sys::WebAssembly::Memory = wasm_bindgen::memory().dyn_into()?;
+    let buffer: js_sys::ArrayBuffer = memory.buffer().dyn_into()?;
+    Ok(buffer.byte_length())
 }
 
 /// open URL in same tab (PWA don't have tabs, only one windows)  
-pub fn open_url(url: &str) {
+pub fn open_url(url: &str) -> Result<(), WebSysError> {
     dbg!(url);
-    window().location().assign(url).unwrap();
+    let location = window()?.location();
+    location.assign(url)?;
     // Strange behavior: if url has hash, then it does not load ?!?
-    match window().location().hash() {
-        Ok(hash) => {
-            dbg!(&hash);
-            window().location().set_hash(&hash).unwrap();
-        }
-        Err(_err) => {}
+    if let Ok(hash) = location.hash() {
+        dbg!(&hash);
+        location.set_hash(&hash)?;
     }
+    Ok(())
+}
+
+/// get the hash fragment of the url, it is not decoded  
+pub fn location_hash() -> Result<String, WebSysError> {
+    Ok(window()?.location().hash()?)
+}
+
+/// Copy the text to the clipboard. The browser can refuse it, so the result of the promise is ignored.  
+/// navigator.clipboard is an unstable API in web_sys, so it is called with Reflect.  
+pub fn copy_text_to_clipboard(text: &str) -> Result<(), WebSysError> {
+    let navigator = window()?.navigator();
+    let clipboard = js_sys::Reflect::get(&navigator, &JsValue::from_str("clipboard"))?;
+    let write_text = js_sys::Reflect::get(&clipboard, &JsValue::from_str("writeText"))?;
+    let write_text = write_text
+        .dyn_into::<js_sys::Function>()
+        .map_err(|_| WebSysError::JavaScript("navigator.clipboard.writeText is not a function".to_string()))?;
+    write_text.call1(&clipboard, &JsValue::from_str(text))?;
+    Ok(())
+}
+
+/// Let the browser download the text as a file.  
+pub fn download_text_file(file_name: &str, text: &str) -> Result<(), WebSysError> {
+    let parts = js_sys::Array::of1(&JsValue::from_str(text));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("text/plain");
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+    let document = window()?.document().ok_or(WebSysError::NoDocument)?;
+    let anchor = document
+        .create_element("a")?
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .map_err(|_| WebSysError::WrongElementType("a".to_string(), "an HtmlAnchorElement"))?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    web_sys::Url::revoke_object_url(&url)?;
+    Ok(())
+}
+
+/// the local storage of the browser  
+fn local_storage() -> Result<web_sys::Storage, WebSysError> {
+    window()?
+        .local_storage()?
+        .ok_or_else(|| WebSysError::JavaScript("localStorage is not available".to_string()))
+}
+
+/// get the value from the local storage  
+pub fn local_storage_get(key: &str) -> Result<Option<String>, WebSysError> {
+    Ok(local_storage()?.get_item(key)?)
+}
+
+/// set the value in the local storage  
+pub fn local_storage_set(key: &str, value: &str) -> Result<(), WebSysError> {
+    Ok(local_storage()?.set_item(key, value)?)
+}
+
+/// remove the value from the local storage  
+pub fn local_storage_remove(key: &str) -> Result<(), WebSysError> {
+    Ok(local_storage()?.remove_item(key)?)
+}
+
+/// Wasm must read date and time from javascript.  
+pub fn now_iso_string() -> String {
+    String::from(js_sys::Date::new_0().to_iso_string())
 }
 
 /// Wasm must read time from javascript.  